@@ -32,6 +32,9 @@ pub fn create_item(attr: TokenStream, item: TokenStream) -> TokenStream {
     let prefix_address = Ident::new(&(arg_str.to_lowercase() + "_address"), Span::call_site());
     let prefix_data = Ident::new(&(arg_str.to_lowercase() + "_data"), Span::call_site());
     let prefix_accounts = Ident::new(&(arg_str.to_lowercase() + "_accounts"), Span::call_site());
+    let prefix_active = Ident::new(&("active_".to_owned() + &arg_str.to_lowercase() + "s"), Span::call_site());
+    let prefix_active_count = Ident::new(&("active_".to_owned() + &arg_str.to_lowercase() + "_count"), Span::call_site());
+    let prefix_active_positions = Ident::new(&("active_".to_owned() + &arg_str.to_lowercase() + "_positions"), Span::call_site());
 
     let expanded = quote! {
         #(#attrs)* #vis #sig {
@@ -41,19 +44,29 @@ pub fn create_item(attr: TokenStream, item: TokenStream) -> TokenStream {
 
             let #prefix_account = Self::env().caller();
 
-            ensure!(!self.data::<Data>().#prefix_data.contains(&#prefix_account), FoodOrderError::AlreadyExist);
+            let tombstone = self.data::<Data>().#prefix_data.get(&#prefix_account);
+            ensure!(tombstone.as_ref().map_or(true, |item| item.deleted), FoodOrderError::AlreadyExist);
 
-            let #prefix_id = self.data::<Data>().#prefix_id;
+            // A deleted account registering again revives its tombstone under the same id
+            let #prefix_id = match tombstone {
+                Some(item) => item.#prefix_id,
+                None => {
+                    let #prefix_id = self.data::<Data>().#prefix_id;
+                    self.data::<Data>().#prefix_id += 1;
+                    #prefix_id
+                }
+            };
             let item = #prefix {
                 #prefix_id,
                 #prefix_account,
                 #prefix_name,
                 #prefix_address,
                 phone_number,
+                ..Default::default()
             };
-            self.data::<Data>().#prefix_id += 1;
             self.data::<Data>().#prefix_data.insert(&#prefix_account, &item);
             self.data::<Data>().#prefix_accounts.insert(&#prefix_id, &#prefix_account);
+            index_insert!(self.data::<Data>(), #prefix_active, #prefix_active_count, #prefix_active_positions, (), #prefix_id);
             
             #(#stmts)*
 
@@ -102,6 +115,7 @@ pub fn read_item(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// read_item_from_id is a procedure macro to encapsulate reusable logic otherwise repeated in separate functions such as:
 /// read_customer_from_id, read_restaurant_from_id, read_courier_from_id, etc.  
 /// It can also be applicable or extendable to other appropriate dApp business items
+/// Callers other than the item owner get a redacted view
 #[proc_macro_attribute]
 pub fn read_item_from_id(attr: TokenStream, item: TokenStream) -> TokenStream {
     let arg_str = attr.to_string();
@@ -131,7 +145,12 @@ pub fn read_item_from_id(attr: TokenStream, item: TokenStream) -> TokenStream {
             
             #(#stmts)*
 
-            Ok(self.data::<Data>().#prefix_data.get(&account).unwrap())
+            let item = self.data::<Data>().#prefix_data.get(&account).unwrap();
+            if account == Self::env().caller() {
+                Ok(item)
+            } else {
+                Ok(item.redacted())
+            }
         }
     };
 
@@ -141,6 +160,7 @@ pub fn read_item_from_id(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// read_item_all is a procedure macro to encapsulate reusable logic otherwise repeated in separate functions such as:
 /// read_customer_all, read_restaurant_all, read_courier_all, etc.  
 /// It can also be applicable or extendable to other appropriate dApp business items
+/// Items that are not deleted are returned a page at a time as redacted views
 #[proc_macro_attribute]
 pub fn read_item_all(attr: TokenStream, item: TokenStream) -> TokenStream {
     let arg_str = attr.to_string();
@@ -159,29 +179,27 @@ pub fn read_item_all(attr: TokenStream, item: TokenStream) -> TokenStream {
     };
     
     let prefix = Ident::new(&arg_str, Span::call_site());
-    let prefix_id = Ident::new(&(arg_str.to_lowercase() + "_id"), Span::call_site());
     let prefix_data = Ident::new(&(arg_str.to_lowercase() + "_data"), Span::call_site());
     let prefix_accounts = Ident::new(&(arg_str.to_lowercase() + "_accounts"), Span::call_site());
+    let prefix_active = Ident::new(&("active_".to_owned() + &arg_str.to_lowercase() + "s"), Span::call_site());
+    let prefix_active_count = Ident::new(&("active_".to_owned() + &arg_str.to_lowercase() + "_count"), Span::call_site());
 
     let expanded = quote! {
         #(#attrs)* #vis #sig {
-            ensure!(from < to, FoodOrderError::InvalidParameters);
-            ensure!(from < self.data::<Data>().#prefix_id, FoodOrderError::InvalidParameters);
+            let total = self.data::<Data>().#prefix_active_count.get(&()).unwrap_or(0);
+            let range = self.data::<Data>().page_range(cursor, limit, total)?;
+            let end = range.end;
 
             let mut list: Vec<#prefix> = Vec::new();
-            let start = max(1, from);
-            let end = min(self.data::<Data>().#prefix_id, to);
-
-            for i in start..end {
-                if self.data::<Data>().#prefix_accounts.contains(&i) {
-                    let account = self.data::<Data>().#prefix_accounts.get(&i).unwrap();
-                    list.push(self.data::<Data>().#prefix_data.get(&account).unwrap())
-                }
+            for i in range {
+                let id = self.data::<Data>().#prefix_active.get(&((), i)).unwrap();
+                let account = self.data::<Data>().#prefix_accounts.get(&id).unwrap();
+                list.push(self.data::<Data>().#prefix_data.get(&account).unwrap().redacted());
             }
 
             #(#stmts)*
 
-            Ok(list)
+            Ok(Page::new(list, end, total))
         }
     };
 
@@ -241,6 +259,7 @@ pub fn update_item(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// delete_item is a procedure macro to encapsulate reusable logic otherwise repeated in separate functions such as:
 /// delete_customer, delete_restaurant, delete_courier, etc.  
 /// It can also be applicable or extendable to other appropriate dApp business items
+/// Deletion is refused while the item still has open orders, and the item is kept as a tombstone
 #[proc_macro_attribute]
 pub fn delete_item(attr: TokenStream, item: TokenStream) -> TokenStream {
     let arg_str = attr.to_string();
@@ -262,7 +281,10 @@ pub fn delete_item(attr: TokenStream, item: TokenStream) -> TokenStream {
     let prefix_id = Ident::new(&(arg_str.to_lowercase() + "_id"), Span::call_site());
     let prefix_account = Ident::new(&(arg_str.to_lowercase() + "_account"), Span::call_site());
     let prefix_data = Ident::new(&(arg_str.to_lowercase() + "_data"), Span::call_site());
-    let prefix_accounts = Ident::new(&(arg_str.to_lowercase() + "_accounts"), Span::call_site());
+    let prefix_open_orders = Ident::new(&(arg_str.to_lowercase() + "_open_orders"), Span::call_site());
+    let prefix_active = Ident::new(&("active_".to_owned() + &arg_str.to_lowercase() + "s"), Span::call_site());
+    let prefix_active_count = Ident::new(&("active_".to_owned() + &arg_str.to_lowercase() + "_count"), Span::call_site());
+    let prefix_active_positions = Ident::new(&("active_".to_owned() + &arg_str.to_lowercase() + "_positions"), Span::call_site());
 
     let expanded = quote! {
         #(#attrs)* #vis #sig {
@@ -270,10 +292,14 @@ pub fn delete_item(attr: TokenStream, item: TokenStream) -> TokenStream {
 
             // ensure!(self.data::<Data>().#prefix_data.contains(&#prefix_account), FoodOrderError::NotExist);
 
-            let #prefix_base = self.data::<Data>().#prefix_data.get(&#prefix_account).unwrap();
+            let mut #prefix_base = self.data::<Data>().#prefix_data.get(&#prefix_account).unwrap();
+
+            ensure!(self.data::<Data>().#prefix_open_orders.get(&#prefix_base.#prefix_id).unwrap_or(0) == 0, FoodOrderError::HasOpenOrders);
 
-            self.data::<Data>().#prefix_data.remove(&#prefix_account);
-            self.data::<Data>().#prefix_accounts.remove(&#prefix_base.#prefix_id);
+            // Keep the record as a tombstone so that past orders and deliveries can still resolve it
+            #prefix_base.deleted = true;
+            self.data::<Data>().#prefix_data.insert(&#prefix_account, &#prefix_base);
+            index_remove!(self.data::<Data>(), #prefix_active, #prefix_active_count, #prefix_active_positions, (), #prefix_base.#prefix_id);
 
             #(#stmts)*
 
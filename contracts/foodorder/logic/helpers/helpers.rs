@@ -10,6 +10,46 @@ macro_rules! ensure {
     }};
 }
 
+/// Append `$value` to the `$key` list of an index kept in chunked storage.
+///
+/// An index is made of three mappings on `Data`: `$entries` holding one value per
+/// `(key, position)` cell, `$count` holding the length of each list and `$positions`
+/// holding the position of each `(key, value)`, so that lists never live under a single
+/// storage key and can grow without hitting the packed layout buffer limit.
+#[macro_export]
+macro_rules! index_insert {
+    ($data:expr, $entries:ident, $count:ident, $positions:ident, $key:expr, $value:expr $(,)?) => {{
+        let key = $key;
+        let value = $value;
+        let count = $data.$count.get(&key).unwrap_or(0);
+        $data.$entries.insert(&(key.clone(), count), &value);
+        $data.$positions.insert(&(key.clone(), value.clone()), &count);
+        $data.$count.insert(&key, &(count + 1));
+    }};
+}
+
+/// Remove `$value` from the `$key` list of an index, moving the last entry into its place.
+///
+/// Does nothing if the value is not in the list. See `index_insert!` for the index layout.
+#[macro_export]
+macro_rules! index_remove {
+    ($data:expr, $entries:ident, $count:ident, $positions:ident, $key:expr, $value:expr $(,)?) => {{
+        let key = $key;
+        let value = $value;
+        if let Some(position) = $data.$positions.get(&(key.clone(), value.clone())) {
+            let last = $data.$count.get(&key).unwrap_or(0) - 1;
+            if position != last {
+                let moved = $data.$entries.get(&(key.clone(), last)).unwrap();
+                $data.$entries.insert(&(key.clone(), position), &moved);
+                $data.$positions.insert(&(key.clone(), moved), &position);
+            }
+            $data.$entries.remove(&(key.clone(), last));
+            $data.$positions.remove(&(key.clone(), value));
+            $data.$count.insert(&key, &last);
+        }
+    }};
+}
+
 #[macro_export]
 macro_rules! transfer_from_contract_to_account {
     ($account:expr, $amount:expr) => {
@@ -1,18 +1,16 @@
 use crud_macro::{create_item, read_item, read_item_from_id, read_item_all, update_item, delete_item};
-use openbrush::traits::Storage;
+use openbrush::traits::{Storage, Timestamp};
 
 use ink::prelude::{vec::Vec, string::String};
 
 use crate::{
-    ensure,
-    impls::data::{Data, CourierId, Courier, FoodOrderError, DeliveryId, DeliveryStatus},
+    ensure, index_insert, index_remove,
+    impls::data::{Data, CourierId, Courier, FoodOrderError, DeliveryId, Delivery, DeliveryStatus, OrderStatus, Redact, OpenDelivery, Page, CourierEarnings},
     traits::events::FoodOrderEvents,
 };
 
 use openbrush::{modifiers, modifier_definition};
 
-use core::cmp::{max, min};
-
 #[openbrush::trait_definition]
 pub trait CourierServiceImpl: Storage<Data> + FoodOrderEvents
 {
@@ -34,19 +32,28 @@ pub trait CourierServiceImpl: Storage<Data> + FoodOrderEvents
 
         // let courier_account = Self::env().caller();
 
-        // ensure!(!self.data::<Data>().courier_data.contains(&courier_account), FoodOrderError::AlreadyExist);
+        // let tombstone = self.data::<Data>().courier_data.get(&courier_account);
+        // ensure!(tombstone.as_ref().map_or(true, |courier| courier.deleted), FoodOrderError::AlreadyExist);
 
-        // let courier_id = self.data::<Data>().courier_id;
+        // let courier_id = match tombstone {
+        //     Some(courier) => courier.courier_id,
+        //     None => {
+        //         let courier_id = self.data::<Data>().courier_id;
+        //         self.data::<Data>().courier_id += 1;
+        //         courier_id
+        //     }
+        // };
         // let courier = Courier {
         //     courier_id,
         //     courier_account,
         //     courier_name,
         //     courier_address,
         //     phone_number,
+        //     ..Default::default()
         // };
-        // self.data::<Data>().courier_id += 1;
         // self.data::<Data>().courier_data.insert(&courier_account, &courier);
         // self.data::<Data>().courier_accounts.insert(&courier_id, &courier_account);
+        // index_insert!(self.data::<Data>(), active_couriers, active_courier_count, active_courier_positions, (), courier_id);
         
         // Ok(courier_id)
     }
@@ -87,14 +94,19 @@ pub trait CourierServiceImpl: Storage<Data> + FoodOrderEvents
         
         // let courier_account = self.data::<Data>().courier_accounts.get(&courier_id).unwrap();
 
-        // Ok(self.data::<Data>().courier_data.get(&courier_account).unwrap())
+        // let courier = self.data::<Data>().courier_data.get(&courier_account).unwrap();
+        // if courier_account == Self::env().caller() {
+        //     Ok(courier)
+        // } else {
+        //     Ok(courier.redacted())
+        // }
     }
 
     /// Function to get couriers from given scope
     /// Use read_item_all procedure macro for Courier
     #[ink(message)]
     #[read_item_all(Courier)]
-    fn read_courier_all(&self, from: CourierId, to: CourierId) -> Result<Vec<Courier>, FoodOrderError> {
+    fn read_courier_all(&self, cursor: u64, limit: u64) -> Result<Page<Courier>, FoodOrderError> {
         // **
 
         // Comments below are current expanded code from the read_item_all macro 
@@ -102,21 +114,18 @@ pub trait CourierServiceImpl: Storage<Data> + FoodOrderEvents
 
         // **
 
-        // ensure!(from < to, FoodOrderError::InvalidParameters);
-        // ensure!(from < self.data::<Data>().courier_id, FoodOrderError::InvalidParameters);
+        // let total = self.data::<Data>().active_courier_count.get(&()).unwrap_or(0);
+        // let range = self.data::<Data>().page_range(cursor, limit, total)?;
+        // let end = range.end;
 
         // let mut courier_list: Vec<Courier> = Vec::new();
-        // let start = max(1, from);
-        // let end = min(self.data::<Data>().courier_id, to);
-
-        // for i in start..end {
-        //     if self.data::<Data>().courier_accounts.contains(&i) {
-        //         let courier_account = self.data::<Data>().courier_accounts.get(&i).unwrap();
-        //         courier_list.push(self.data::<Data>().courier_data.get(&courier_account).unwrap())
-        //     }
+        // for i in range {
+        //     let courier_id = self.data::<Data>().active_couriers.get(&((), i)).unwrap();
+        //     let courier_account = self.data::<Data>().courier_accounts.get(&courier_id).unwrap();
+        //     courier_list.push(self.data::<Data>().courier_data.get(&courier_account).unwrap().redacted());
         // }
 
-        // Ok(courier_list)
+        // Ok(Page::new(courier_list, end, total))
     }
 
     /// Function to update a courier
@@ -167,38 +176,222 @@ pub trait CourierServiceImpl: Storage<Data> + FoodOrderEvents
 
         // ensure!(self.data::<Data>().courier_data.contains(&courier_account), FoodOrderError::NotExist);
 
-        // let courier = self.data::<Data>().courier_data.get(&courier_account).unwrap();
+        // let mut courier = self.data::<Data>().courier_data.get(&courier_account).unwrap();
 
-        // self.data::<Data>().courier_data.remove(&courier_account);
-        // self.data::<Data>().courier_accounts.remove(&courier.courier_id);
+        // ensure!(self.data::<Data>().courier_open_orders.get(&courier.courier_id).unwrap_or(0) == 0, FoodOrderError::HasOpenOrders);
+
+        // courier.deleted = true;
+        // self.data::<Data>().courier_data.insert(&courier_account, &courier);
+        // index_remove!(self.data::<Data>(), active_couriers, active_courier_count, active_courier_positions, (), courier.courier_id);
 
         // Ok(())
     }
 
-    /// Function that a courier picks up food at a restaurant for delivery
+    /// Function that a courier reserves a delivery, the food is picked up later with confirm_pickup
+    /// Deliveries offered to another courier can be taken once the offer expired
     #[ink(message)]
     #[modifiers(is_courier)]
-    fn pickup_delivery(&mut self, delivery_id: DeliveryId) -> Result<DeliveryId, FoodOrderError> {
+    fn claim_delivery(&mut self, delivery_id: DeliveryId) -> Result<DeliveryId, FoodOrderError> {
         let courier_account = Self::env().caller();
         // ensure!(self.data::<Data>().courier_data.contains(&courier_account), FoodOrderError::NotExist);
         ensure!(self.data::<Data>().delivery_data.contains(&delivery_id), FoodOrderError::DeliveryNotExist);
 
         let mut delivery = self.data::<Data>().delivery_data.get(&delivery_id).unwrap();
-        ensure!(delivery.status == DeliveryStatus::Waiting, FoodOrderError::DeliveryStatusNotWaiting);
+        ensure!(self.data::<Data>().is_up_for_grabs(&delivery, Self::env().block_timestamp()), FoodOrderError::DeliveryStatusNotWaiting);
+        self.data::<Data>().ensure_no_courier_conflict(&courier_account, &delivery)?;
 
         let courier_id = self.data::<Data>().courier_data.get(&courier_account).unwrap().courier_id;
-        delivery.status = DeliveryStatus::PickedUp;
-        delivery.courier_id = courier_id;
+        self.data::<Data>().assign_delivery(&mut delivery, courier_id);
+
+        self.emit_claim_delivery_event(delivery_id, courier_id);
+
+        Ok(delivery_id)
+    }
+
+    /// Function that a courier confirms it picked up the food of a claimed delivery
+    /// Only possible once the food is prepared and the restaurant acknowledged the handover
+    #[ink(message)]
+    #[modifiers(is_courier)]
+    fn confirm_pickup(&mut self, delivery_id: DeliveryId) -> Result<DeliveryId, FoodOrderError> {
+        ensure!(self.data::<Data>().delivery_data.contains(&delivery_id), FoodOrderError::DeliveryNotExist);
+
+        let courier_account = Self::env().caller();
+        let courier_id = self.data::<Data>().courier_data.get(&courier_account).unwrap().courier_id;
+
+        let mut delivery = self.data::<Data>().delivery_data.get(&delivery_id).unwrap();
+        ensure!(delivery.courier_id == courier_id, FoodOrderError::CallerIsNotDeliveryCourier);
+        ensure!(delivery.status == DeliveryStatus::HandoverAcknowledged, FoodOrderError::DeliveryStatusNotHandedOver);
+        ensure!(self.data::<Data>().order_data.get(&delivery.order_id).unwrap().status == OrderStatus::FoodPrepared, FoodOrderError::OrderStatusNotPrepared);
+
+        self.data::<Data>().set_delivery_status(&mut delivery, DeliveryStatus::PickedUp);
+        delivery.picked_up_at = Self::env().block_timestamp();
         self.data::<Data>().delivery_data.insert(&delivery_id, &delivery);
 
-        let mut order = self.data::<Data>().order_data.get(&delivery.order_id).unwrap();
-        order.courier_id = courier_id;
-        self.data::<Data>().order_data.insert(&order.order_id, &order);
+        let courier_delivery_count = self.data::<Data>().courier_delivery_count.get(&courier_id).unwrap_or(0);
+        self.data::<Data>().courier_deliveries.insert(&(courier_id, courier_delivery_count), &delivery_id);
+        self.data::<Data>().courier_delivery_count.insert(&courier_id, &(courier_delivery_count + 1));
+
+        self.emit_pickup_delivery_event(delivery_id, courier_id);
+
+        Ok(delivery_id)
+    }
+
+    /// Function that a courier gives up a delivery it claimed, putting it back on the open board
+    /// Only possible before the restaurant acknowledged the handover
+    #[ink(message)]
+    #[modifiers(is_courier)]
+    fn release_claim(&mut self, delivery_id: DeliveryId) -> Result<DeliveryId, FoodOrderError> {
+        ensure!(self.data::<Data>().delivery_data.contains(&delivery_id), FoodOrderError::DeliveryNotExist);
+
+        let courier_account = Self::env().caller();
+        let courier_id = self.data::<Data>().courier_data.get(&courier_account).unwrap().courier_id;
+
+        let mut delivery = self.data::<Data>().delivery_data.get(&delivery_id).unwrap();
+        ensure!(delivery.courier_id == courier_id, FoodOrderError::CallerIsNotDeliveryCourier);
+        ensure!(delivery.status == DeliveryStatus::Claimed, FoodOrderError::DeliveryStatusNotClaimed);
+
+        self.data::<Data>().release_delivery(&mut delivery);
+
+        self.emit_claim_released_event(delivery_id, courier_id);
+
+        Ok(delivery_id)
+    }
+
+    /// Function that a courier accepts a delivery a restaurant offered to it, before the offer expires
+    #[ink(message)]
+    #[modifiers(is_courier)]
+    fn accept_courier_offer(&mut self, delivery_id: DeliveryId) -> Result<DeliveryId, FoodOrderError> {
+        ensure!(self.data::<Data>().delivery_data.contains(&delivery_id), FoodOrderError::DeliveryNotExist);
+
+        let courier_account = Self::env().caller();
+        let courier_id = self.data::<Data>().courier_data.get(&courier_account).unwrap().courier_id;
+
+        let mut delivery = self.data::<Data>().delivery_data.get(&delivery_id).unwrap();
+        ensure!(delivery.status == DeliveryStatus::Offered, FoodOrderError::DeliveryStatusNotOffered);
+        ensure!(delivery.offered_to == courier_id, FoodOrderError::CallerIsNotOfferedCourier);
+        ensure!(Self::env().block_timestamp() <= delivery.offer_expires_at, FoodOrderError::OfferExpired);
+
+        self.data::<Data>().assign_delivery(&mut delivery, courier_id);
 
-        self.emit_pickup_delivery_event(delivery_id, delivery.order_id);
+        self.emit_claim_delivery_event(delivery_id, courier_id);
 
         Ok(delivery_id)
     }
+
+    /// Function that a courier declines a delivery a restaurant offered to it, putting it back on the open board
+    #[ink(message)]
+    #[modifiers(is_courier)]
+    fn decline_courier_offer(&mut self, delivery_id: DeliveryId) -> Result<DeliveryId, FoodOrderError> {
+        ensure!(self.data::<Data>().delivery_data.contains(&delivery_id), FoodOrderError::DeliveryNotExist);
+
+        let courier_account = Self::env().caller();
+        let courier_id = self.data::<Data>().courier_data.get(&courier_account).unwrap().courier_id;
+
+        let mut delivery = self.data::<Data>().delivery_data.get(&delivery_id).unwrap();
+        ensure!(delivery.status == DeliveryStatus::Offered, FoodOrderError::DeliveryStatusNotOffered);
+        ensure!(delivery.offered_to == courier_id, FoodOrderError::CallerIsNotOfferedCourier);
+
+        self.data::<Data>().withdraw_offer(&mut delivery);
+        self.data::<Data>().delivery_data.insert(&delivery_id, &delivery);
+
+        self.emit_courier_offer_withdrawn_event(delivery_id, courier_id);
+
+        Ok(delivery_id)
+    }
+
+    /// Function to put a delivery whose courier offer expired back on the open board, anyone can call it
+    #[ink(message)]
+    fn expire_courier_offer(&mut self, delivery_id: DeliveryId) -> Result<DeliveryId, FoodOrderError> {
+        ensure!(self.data::<Data>().delivery_data.contains(&delivery_id), FoodOrderError::DeliveryNotExist);
+
+        let mut delivery = self.data::<Data>().delivery_data.get(&delivery_id).unwrap();
+        ensure!(delivery.status == DeliveryStatus::Offered, FoodOrderError::DeliveryStatusNotOffered);
+        ensure!(Self::env().block_timestamp() > delivery.offer_expires_at, FoodOrderError::OfferNotExpired);
+
+        let courier_id = delivery.offered_to;
+        self.data::<Data>().withdraw_offer(&mut delivery);
+        self.data::<Data>().delivery_data.insert(&delivery_id, &delivery);
+
+        self.emit_courier_offer_withdrawn_event(delivery_id, courier_id);
+
+        Ok(delivery_id)
+    }
+
+    /// Function to get the public projection of a delivery waiting for a courier or offered to one
+    #[ink(message)]
+    fn get_open_delivery(&self, delivery_id: DeliveryId) -> Result<OpenDelivery, FoodOrderError> {
+        ensure!(self.data::<Data>().delivery_data.contains(&delivery_id), FoodOrderError::DeliveryNotExist);
+
+        let delivery = self.data::<Data>().delivery_data.get(&delivery_id).unwrap();
+        ensure!(delivery.status == DeliveryStatus::Waiting || delivery.status == DeliveryStatus::Offered, FoodOrderError::DeliveryStatusNotWaiting);
+
+        Ok(self.data::<Data>().open_delivery(&delivery))
+    }
+
+    /// Function to list deliveries waiting for a courier, with the restaurant location and the payout on offer
+    /// Deliveries whose courier offer expired are listed after the waiting ones, whether or not expire_courier_offer was called
+    /// At most `limit` deliveries, capped by the maximum page size, are scanned per call, pass 0 as `cursor` to start from the beginning,
+    /// offers still running are skipped so a page may hold fewer deliveries
+    #[ink(message)]
+    fn list_open_deliveries(&self, cursor: u64, limit: u64) -> Result<Page<OpenDelivery>, FoodOrderError> {
+        let waiting = self.data::<Data>().status_delivery_count.get(&DeliveryStatus::Waiting).unwrap_or(0);
+        let offered = self.data::<Data>().status_delivery_count.get(&DeliveryStatus::Offered).unwrap_or(0);
+        let total = waiting + offered;
+        let range = self.data::<Data>().page_range(cursor, limit, total)?;
+        let end = range.end;
+
+        let now = Self::env().block_timestamp();
+        let mut delivery_list: Vec<OpenDelivery> = Vec::new();
+        for i in range {
+            let delivery_id = if i < waiting {
+                self.data::<Data>().status_deliveries.get(&(DeliveryStatus::Waiting, i)).unwrap()
+            } else {
+                self.data::<Data>().status_deliveries.get(&(DeliveryStatus::Offered, i - waiting)).unwrap()
+            };
+            let delivery = self.data::<Data>().delivery_data.get(&delivery_id).unwrap();
+            if self.data::<Data>().is_up_for_grabs(&delivery, now) {
+                delivery_list.push(self.data::<Data>().open_delivery(&delivery));
+            }
+        }
+
+        Ok(Page::new(delivery_list, end, total))
+    }
+
+    /// Function to get the deliveries the caller picked up, newest-first
+    /// At most `limit` deliveries, capped by the maximum page size, are returned per call, pass 0 as `cursor` to start from the newest delivery
+    /// As with customer orders the cursor is an upper bound, so deliveries picked up while paging are not repeated
+    #[ink(message)]
+    fn get_courier_deliveries(&self, cursor: u64, limit: u64) -> Result<Page<Delivery>, FoodOrderError> {
+        let courier_account = Self::env().caller();
+
+        ensure!(self.data::<Data>().courier_data.contains(&courier_account), FoodOrderError::NotExist);
+
+        let courier_id = self.data::<Data>().courier_data.get(&courier_account).unwrap().courier_id;
+        let total = self.data::<Data>().courier_delivery_count.get(&courier_id).unwrap_or(0);
+        let range = self.data::<Data>().page_range_newest_first(cursor, limit, total)?;
+        let end = range.start;
+
+        let mut delivery_list: Vec<Delivery> = Vec::new();
+        for i in range.rev() {
+            let delivery_id = self.data::<Data>().courier_deliveries.get(&(courier_id, i)).unwrap();
+            delivery_list.push(self.data::<Data>().delivery_data.get(&delivery_id).unwrap());
+        }
+
+        Ok(Page::newest_first(delivery_list, end, total))
+    }
+
+    /// Function to sum the payouts the caller received for deliveries paid between `from_ts` and `to_ts` inclusive
+    #[ink(message)]
+    fn get_courier_earnings(&self, from_ts: Timestamp, to_ts: Timestamp) -> Result<CourierEarnings, FoodOrderError> {
+        let courier_account = Self::env().caller();
+
+        ensure!(self.data::<Data>().courier_data.contains(&courier_account), FoodOrderError::NotExist);
+        ensure!(from_ts <= to_ts, FoodOrderError::InvalidParameters);
+
+        let courier_id = self.data::<Data>().courier_data.get(&courier_account).unwrap().courier_id;
+
+        Ok(self.data::<Data>().courier_earnings(courier_id, from_ts, to_ts))
+    }
 }
 
 
@@ -213,7 +406,8 @@ where
         instance
             .data()
             .courier_data
-            .contains(&T::env().caller()),
+            .get(&T::env().caller())
+            .map_or(false, |courier| !courier.deleted),
         FoodOrderError::NotExist,
     );
     body(instance)
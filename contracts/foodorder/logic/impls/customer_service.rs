@@ -1,11 +1,12 @@
 use crud_macro::{create_item, read_item, read_item_from_id, read_item_all, update_item, delete_item};
-use openbrush::traits::Storage;
+use openbrush::traits::{Storage, Timestamp};
 use ink::prelude::{vec::Vec, string::String};
 
 use crate::{
-    ensure,
+    ensure, index_insert, index_remove,
     impls::data::{
-        Data, CustomerId, Customer, FoodOrderError, FoodId, DeliveryId, OrderId, Order, OrderStatus, DeliveryStatus
+        Data, CustomerId, Customer, FoodOrderError, FoodId, DeliveryId, OrderId, Order, OrderStatus, DeliveryStatus,
+        EncryptedData, Redact, Page, SelectedOption,
     },
     impls::payment_service::PaymentServiceImpl,
     traits::events::FoodOrderEvents,
@@ -13,8 +14,6 @@ use crate::{
 
 use openbrush::{modifiers, modifier_definition};
 
-use core::cmp::{max, min};
-
 #[openbrush::trait_definition]
 pub trait CustomerServiceImpl: Storage<Data> + FoodOrderEvents + PaymentServiceImpl
 {
@@ -22,7 +21,7 @@ pub trait CustomerServiceImpl: Storage<Data> + FoodOrderEvents + PaymentServiceI
     /// Use create_item procedure macro for Customer
     #[ink(message)]
     #[create_item(Customer)]
-    fn create_customer(&mut self, customer_name: String, customer_address: String, phone_number: String) -> Result<CustomerId, FoodOrderError> {
+    fn create_customer(&mut self, customer_name: String, customer_address: EncryptedData, phone_number: EncryptedData) -> Result<CustomerId, FoodOrderError> {
         // **
 
         // Comments below are current expanded code from the create_item macro 
@@ -36,19 +35,28 @@ pub trait CustomerServiceImpl: Storage<Data> + FoodOrderEvents + PaymentServiceI
 
         // let customer_account = Self::env().caller();
 
-        // ensure!(!self.data::<Data>().customer_data.contains(&customer_account), FoodOrderError::AlreadyExist);
+        // let tombstone = self.data::<Data>().customer_data.get(&customer_account);
+        // ensure!(tombstone.as_ref().map_or(true, |customer| customer.deleted), FoodOrderError::AlreadyExist);
 
-        // let customer_id = self.data::<Data>().customer_id;
+        // let customer_id = match tombstone {
+        //     Some(customer) => customer.customer_id,
+        //     None => {
+        //         let customer_id = self.data::<Data>().customer_id;
+        //         self.data::<Data>().customer_id += 1;
+        //         customer_id
+        //     }
+        // };
         // let customer = Customer {
         //     customer_id,
         //     customer_account,
         //     customer_name,
         //     customer_address,
         //     phone_number,
+        //     ..Default::default()
         // };
-        // self.data::<Data>().customer_id += 1;
         // self.data::<Data>().customer_data.insert(&customer_account, &customer);
         // self.data::<Data>().customer_accounts.insert(&customer_id, &customer_account);
+        // index_insert!(self.data::<Data>(), active_customers, active_customer_count, active_customer_positions, (), customer_id);
         
         // Ok(customer_id)
     }
@@ -88,14 +96,19 @@ pub trait CustomerServiceImpl: Storage<Data> + FoodOrderEvents + PaymentServiceI
         
         // let customer_account = self.data::<Data>().customer_accounts.get(&customer_id).unwrap();
 
-        // Ok(self.data::<Data>().customer_data.get(&customer_account).unwrap())
+        // let customer = self.data::<Data>().customer_data.get(&customer_account).unwrap();
+        // if customer_account == Self::env().caller() {
+        //     Ok(customer)
+        // } else {
+        //     Ok(customer.redacted())
+        // }
     }
 
     /// Function to read customers from given scope
     /// Use read_item_all procedure macro for Customer
     #[ink(message)]
     #[read_item_all(Customer)]
-    fn read_customer_all(&self, from: CustomerId, to: CustomerId) -> Result<Vec<Customer>, FoodOrderError> {
+    fn read_customer_all(&self, cursor: u64, limit: u64) -> Result<Page<Customer>, FoodOrderError> {
         // **
 
         // Comments below are current expanded code from the read_item_all macro 
@@ -103,21 +116,18 @@ pub trait CustomerServiceImpl: Storage<Data> + FoodOrderEvents + PaymentServiceI
 
         // **
 
-        // ensure!(from < to, FoodOrderError::InvalidParameters);
-        // ensure!(from < self.data::<Data>().customer_id, FoodOrderError::InvalidParameters);
+        // let total = self.data::<Data>().active_customer_count.get(&()).unwrap_or(0);
+        // let range = self.data::<Data>().page_range(cursor, limit, total)?;
+        // let end = range.end;
 
         // let mut customer_list: Vec<Customer> = Vec::new();
-        // let start = max(1, from);
-        // let end = min(self.data::<Data>().customer_id, to);
-
-        // for i in start..end {
-        //     if self.data::<Data>().customer_accounts.contains(&i) {
-        //         let customer_account = self.data::<Data>().customer_accounts.get(&i).unwrap();
-        //         customer_list.push(self.data::<Data>().customer_data.get(&customer_account).unwrap())
-        //     }
+        // for i in range {
+        //     let customer_id = self.data::<Data>().active_customers.get(&((), i)).unwrap();
+        //     let customer_account = self.data::<Data>().customer_accounts.get(&customer_id).unwrap();
+        //     customer_list.push(self.data::<Data>().customer_data.get(&customer_account).unwrap().redacted());
         // }
 
-        // Ok(customer_list)
+        // Ok(Page::new(customer_list, end, total))
     }
 
     /// Function to update a customer
@@ -125,7 +135,7 @@ pub trait CustomerServiceImpl: Storage<Data> + FoodOrderEvents + PaymentServiceI
     #[ink(message)]
     #[update_item(Customer)]
     #[modifiers(is_customer)]
-    fn update_customer(&mut self, customer_name: String, customer_address: String, phone_number: String) -> Result<(), FoodOrderError> {
+    fn update_customer(&mut self, customer_name: String, customer_address: EncryptedData, phone_number: EncryptedData) -> Result<(), FoodOrderError> {
         // **
 
         // Comments below are current expanded code from the update_item macro 
@@ -168,28 +178,58 @@ pub trait CustomerServiceImpl: Storage<Data> + FoodOrderEvents + PaymentServiceI
 
         // ensure!(self.data::<Data>().customer_data.contains(&customer_account), FoodOrderError::NotExist);
 
-        // let customer = self.data::<Data>().customer_data.get(&customer_account).unwrap();
+        // let mut customer = self.data::<Data>().customer_data.get(&customer_account).unwrap();
 
-        // self.data::<Data>().customer_data.remove(&customer_account);
-        // self.data::<Data>().customer_accounts.remove(&customer.customer_id);
+        // ensure!(self.data::<Data>().customer_open_orders.get(&customer.customer_id).unwrap_or(0) == 0, FoodOrderError::HasOpenOrders);
+
+        // customer.deleted = true;
+        // self.data::<Data>().customer_data.insert(&customer_account, &customer);
+        // index_remove!(self.data::<Data>(), active_customers, active_customer_count, active_customer_positions, (), customer.customer_id);
 
         // Ok(())
     }
 
     /// Function that a customer submits an order
+    /// Pass a future `scheduled_for` time to order ahead, the payment stays in escrow until the order is fulfilled or cancelled
     #[ink(message, payable)]
     #[modifiers(is_customer)]
-    fn submit_order(&mut self, food_id: FoodId, delivery_address: String) -> Result<OrderId, FoodOrderError> {
+    fn submit_order(&mut self, food_id: FoodId, options: Vec<SelectedOption>, delivery_address: EncryptedData, scheduled_for: Option<Timestamp>) -> Result<OrderId, FoodOrderError> {
         let customer_account = Self::env().caller();
         let price = Self::env().transferred_value();
+        let now = Self::env().block_timestamp();
 
         // ensure!(self.data::<Data>().customer_data.contains(&customer_account), FoodOrderError::NotExist);
         ensure!(self.data::<Data>().food_data.contains(&food_id), FoodOrderError::FoodNotExist);
         ensure!(delivery_address.len() > 0, FoodOrderError::InvalidAddressLength);
-        ensure!(price == self.data::<Data>().food_data.get(&food_id).unwrap().food_price.into(), FoodOrderError::NotSamePrice);
+        if let Some(scheduled_for) = scheduled_for {
+            ensure!(scheduled_for > now, FoodOrderError::InvalidScheduledTime);
+        }
+
+        let mut food = self.data::<Data>().food_data.get(&food_id).unwrap();
+        let (food_price, options) = self.data::<Data>().price_with_options(&food, &options)?;
+        ensure!(price == food_price, FoodOrderError::NotSamePrice);
+        ensure!(food.available, FoodOrderError::FoodNotAvailable);
+        ensure!(food.stock != Some(0), FoodOrderError::FoodSoldOut);
 
         let customer_id = self.data::<Data>().customer_data.get(&customer_account).unwrap().customer_id;
-        let restaurant_id = self.data::<Data>().food_data.get(&food_id).unwrap().restaurant_id;
+        let restaurant_id = food.restaurant_id;
+
+        // Foods of a deleted restaurant stay readable but can no longer be ordered
+        let restaurant_account = self.data::<Data>().restaurant_accounts.get(&restaurant_id).unwrap();
+        let restaurant = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap();
+        ensure!(!restaurant.deleted, FoodOrderError::RestaurantNotActive);
+        ensure!(self.data::<Data>().is_open(&restaurant, scheduled_for.unwrap_or(now)), FoodOrderError::RestaurantClosed);
+        self.data::<Data>().ensure_capacity_on_submit(&restaurant, scheduled_for)?;
+
+        if !self.data::<Data>().conflict_overrides.contains(&customer_account) {
+            let rules = self.data::<Data>().conflict_rules.clone();
+            ensure!(!(rules.forbid_customer_restaurant && customer_account == restaurant_account), FoodOrderError::ConflictOfInterest);
+        }
+
+        if let Some(stock) = food.stock {
+            food.stock = Some(stock - 1);
+            self.data::<Data>().food_data.insert(&food_id, &food);
+        }
 
         let order_id = self.data::<Data>().order_id;
         let order = Order {
@@ -201,19 +241,90 @@ pub trait CustomerServiceImpl: Storage<Data> + FoodOrderEvents + PaymentServiceI
             delivery_id: 0,
             delivery_address,
             status: OrderStatus::OrderSubmitted,
-            timestamp: Self::env().block_timestamp(),
+            timestamp: now,
             price,
             eta: 0,
+            options,
+            food_name: food.food_name,
+            unit_price: food.food_price,
+            food_version: food.version,
+            restaurant_name: restaurant.restaurant_name,
+            scheduled_for,
         };
         self.data::<Data>().order_id += 1;
         self.data::<Data>().order_data.insert(&order_id, &order);
 
+        self.data::<Data>().enqueue_restaurant_order(&order, OrderStatus::OrderSubmitted);
+        self.data::<Data>().update_stats(&order, |stats| stats.orders_submitted += 1);
+
+        let customer_open_orders = self.data::<Data>().customer_open_orders.get(&customer_id).unwrap_or(0);
+        self.data::<Data>().customer_open_orders.insert(&customer_id, &(customer_open_orders + 1));
+
+        let customer_order_count = self.data::<Data>().customer_order_count.get(&customer_id).unwrap_or(0);
+        self.data::<Data>().customer_orders.insert(&(customer_id, customer_order_count), &order_id);
+        self.data::<Data>().customer_order_count.insert(&customer_id, &(customer_order_count + 1));
+        let restaurant_open_orders = self.data::<Data>().restaurant_open_orders.get(&restaurant_id).unwrap_or(0);
+        self.data::<Data>().restaurant_open_orders.insert(&restaurant_id, &(restaurant_open_orders + 1));
+
         self.emit_submit_order_event(order_id, customer_account);
 
         Ok(order_id)
 
     }
 
+    /// Function to get the caller's orders newest-first, optionally only those in the given status
+    /// At most `limit` orders, capped by the maximum page size, are scanned per call, pass 0 as `cursor` to start from the newest order
+    /// Unlike other list queries the cursor is an upper bound, so orders submitted while paging are not repeated
+    #[ink(message)]
+    fn get_customer_orders(&self, cursor: u64, limit: u64, status: Option<OrderStatus>) -> Result<Page<Order>, FoodOrderError> {
+        let customer_account = Self::env().caller();
+
+        ensure!(self.data::<Data>().customer_data.contains(&customer_account), FoodOrderError::NotExist);
+
+        let customer_id = self.data::<Data>().customer_data.get(&customer_account).unwrap().customer_id;
+        let total = self.data::<Data>().customer_order_count.get(&customer_id).unwrap_or(0);
+        let range = self.data::<Data>().page_range_newest_first(cursor, limit, total)?;
+        let end = range.start;
+
+        let mut order_list: Vec<Order> = Vec::new();
+        for i in range.rev() {
+            let order_id = self.data::<Data>().customer_orders.get(&(customer_id, i)).unwrap();
+            let order = self.data::<Data>().order_data.get(&order_id).unwrap();
+            if status.as_ref().map_or(true, |status| order.status == *status) {
+                order_list.push(order);
+            }
+        }
+
+        Ok(Page::newest_first(order_list, end, total))
+    }
+
+    /// Function that a customer cancels a scheduled order for free and gets the payment back from escrow
+    /// Only possible before the lead window opens, or once the requested time passed without the restaurant confirming
+    #[ink(message)]
+    #[modifiers(is_customer)]
+    fn cancel_order(&mut self, order_id: OrderId) -> Result<OrderId, FoodOrderError> {
+        ensure!(self.data::<Data>().order_data.contains(&order_id), FoodOrderError::OrderNotExist);
+
+        let customer_account = Self::env().caller();
+        let customer_id = self.data::<Data>().customer_data.get(&customer_account).unwrap().customer_id;
+
+        let mut order = self.data::<Data>().order_data.get(&order_id).unwrap();
+        ensure!(order.customer_id == customer_id, FoodOrderError::CallerIsNotCustomerOrder);
+        ensure!(order.status == OrderStatus::OrderSubmitted, FoodOrderError::OrderStatusNotSubmitted);
+        ensure!(order.scheduled_for.is_some(), FoodOrderError::OrderNotCancellable);
+        ensure!(!self.data::<Data>().in_lead_window(&order, Self::env().block_timestamp()), FoodOrderError::OrderNotCancellable);
+
+        self.data::<Data>().set_order_status(&mut order, OrderStatus::OrderCancelled);
+        self.data::<Data>().order_data.insert(&order_id, &order);
+        self.data::<Data>().release_order(&order);
+
+        self.emit_cancel_order_event(order_id);
+
+        PaymentServiceImpl::transfer_to(self, customer_account, order.price).expect("Err");
+
+        Ok(order_id)
+    }
+
     /// Function that a customer accepts its delivery
     #[ink(message)]
     #[modifiers(is_customer)]
@@ -226,23 +337,34 @@ pub trait CustomerServiceImpl: Storage<Data> + FoodOrderEvents + PaymentServiceI
         let customer_id = self.data::<Data>().customer_data.get(&customer_account).unwrap().customer_id;
         let order_id = self.data::<Data>().delivery_data.get(&delivery_id).unwrap().order_id;
 
-        ensure!(self.data::<Data>().order_data.get(&delivery_id).unwrap().status == OrderStatus::FoodDelivered, FoodOrderError::OrderStatusNotDelivered);
-        ensure!(self.data::<Data>().delivery_data.get(&order_id).unwrap().status == DeliveryStatus::PickedUp, FoodOrderError::DeliveryStatusNotPickUp);
+        ensure!(self.data::<Data>().order_data.get(&order_id).unwrap().status == OrderStatus::FoodDelivered, FoodOrderError::OrderStatusNotDelivered);
+        ensure!(self.data::<Data>().delivery_data.get(&delivery_id).unwrap().status == DeliveryStatus::PickedUp, FoodOrderError::DeliveryStatusNotPickUp);
         ensure!(self.data::<Data>().order_data.get(&order_id).unwrap().customer_id == customer_id, FoodOrderError::CallerIsNotCustomerOrder);
 
         let mut order = self.data::<Data>().order_data.get(&order_id).unwrap();
-        order.status = OrderStatus::DeliveryAccepted;
+        self.data::<Data>().set_order_status(&mut order, OrderStatus::DeliveryAccepted);
         self.data::<Data>().order_data.insert(&order_id, &order);
 
         let mut delivery = self.data::<Data>().delivery_data.get(&delivery_id).unwrap();
-        delivery.status = DeliveryStatus::Accepted;
+        self.data::<Data>().set_delivery_status(&mut delivery, DeliveryStatus::Accepted);
+        delivery.paid_at = Self::env().block_timestamp();
+        delivery.payout = self.data::<Data>().courier_payout(&order);
         self.data::<Data>().delivery_data.insert(&delivery_id, &delivery);
 
+        self.data::<Data>().record_courier_payment(delivery.courier_id, delivery_id, delivery.payout);
+
         self.emit_accept_delivery_event(delivery_id, order_id);
 
+        let customer_open_orders = self.data::<Data>().customer_open_orders.get(&order.customer_id).unwrap_or(0);
+        self.data::<Data>().customer_open_orders.insert(&order.customer_id, &customer_open_orders.saturating_sub(1));
+        let restaurant_open_orders = self.data::<Data>().restaurant_open_orders.get(&order.restaurant_id).unwrap_or(0);
+        self.data::<Data>().restaurant_open_orders.insert(&order.restaurant_id, &restaurant_open_orders.saturating_sub(1));
+        let courier_open_orders = self.data::<Data>().courier_open_orders.get(&delivery.courier_id).unwrap_or(0);
+        self.data::<Data>().courier_open_orders.insert(&delivery.courier_id, &courier_open_orders.saturating_sub(1));
+
         // Transfer money to courier.
         let courier_account = self.data::<Data>().courier_accounts.get(&delivery.courier_id).unwrap();
-        let amount = order.price / (self.data::<Data>().fee_rate as u128);
+        let amount = delivery.payout;
         
         PaymentServiceImpl::transfer_to(self, courier_account, amount).expect("Err");
 
@@ -262,7 +384,8 @@ where
         instance
             .data()
             .customer_data
-            .contains(&T::env().caller()),
+            .get(&T::env().caller())
+            .map_or(false, |customer| !customer.deleted),
         FoodOrderError::NotExist,
     );
     body(instance)
@@ -1,4 +1,6 @@
-use ink::prelude::string::String;
+use ink::prelude::{vec, vec::Vec, string::String};
+use crate::{ensure, index_insert, index_remove};
+use core::{cmp::min, ops::Range};
 use openbrush::traits::Timestamp;
 use openbrush::{storage::Mapping, contracts::ownable::OwnableError, traits::{AccountId, Balance}};
 
@@ -8,6 +10,41 @@ pub type DeliveryId = u64;
 pub type CustomerId = u64;
 pub type CourierId = u64;
 pub type RestaurantId = u64;
+pub type CategoryId = u64;
+pub type SectionId = u64;
+
+pub const MAX_RESTAURANT_CATEGORIES: usize = 8;
+pub const MAX_OPTION_GROUPS: usize = 8;
+pub const MAX_OPTION_CHOICES: usize = 16;
+pub const MAX_OPENING_INTERVALS: usize = 28;
+pub const MAX_HOLIDAYS: usize = 32;
+pub const MAX_MENU_SECTIONS: u64 = 16;
+pub const MAX_SECTION_FOODS: u64 = 64;
+pub const MAX_CID_LENGTH: usize = 96;
+
+/// Dietary attributes of a food, combined as a bitmask in `Food::dietary_flags`
+pub const DIET_VEGAN: u32 = 1 << 0;
+pub const DIET_VEGETARIAN: u32 = 1 << 1;
+pub const DIET_HALAL: u32 = 1 << 2;
+pub const DIET_KOSHER: u32 = 1 << 3;
+pub const DIET_GLUTEN_FREE: u32 = 1 << 4;
+
+/// Allergens contained in a food, combined as a bitmask in `Food::allergens`
+pub const ALLERGEN_NUTS: u32 = 1 << 0;
+pub const ALLERGEN_PEANUTS: u32 = 1 << 1;
+pub const ALLERGEN_DAIRY: u32 = 1 << 2;
+pub const ALLERGEN_EGGS: u32 = 1 << 3;
+pub const ALLERGEN_GLUTEN: u32 = 1 << 4;
+pub const ALLERGEN_SOY: u32 = 1 << 5;
+pub const ALLERGEN_FISH: u32 = 1 << 6;
+pub const ALLERGEN_SHELLFISH: u32 = 1 << 7;
+pub const ALLERGEN_SESAME: u32 = 1 << 8;
+
+const MINUTE: Timestamp = 60_000;
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+/// Personal data encrypted off-chain by the client, the contract only stores the ciphertext
+pub type EncryptedData = Vec<u8>;
 
 #[derive(scale::Decode, scale::Encode, Debug, Clone, Eq, PartialEq)]
 #[cfg_attr(
@@ -20,6 +57,8 @@ pub enum OrderStatus {
     FoodPrepared,
     FoodDelivered,
     DeliveryAccepted,
+    OrderRejected,
+    OrderCancelled,
 }
 
 #[derive(scale::Decode, scale::Encode, Debug, Clone, Eq, PartialEq)]
@@ -31,6 +70,9 @@ pub enum DeliveryStatus {
     Waiting,
     PickedUp,
     Accepted,
+    Offered,
+    Claimed,
+    HandoverAcknowledged,
 }
 
 #[derive(scale::Decode, scale::Encode, Debug, Clone, Eq, PartialEq)]
@@ -42,8 +84,9 @@ pub struct Customer {
     pub customer_id: CustomerId,
     pub customer_account: AccountId,
     pub customer_name: String,
-    pub customer_address: String,
-    pub phone_number: String,
+    pub customer_address: EncryptedData,
+    pub phone_number: EncryptedData,
+    pub deleted: bool,
 }
 
 impl Default for Customer {
@@ -54,6 +97,7 @@ impl Default for Customer {
             customer_name: Default::default(),
             customer_address: Default::default(),
             phone_number: Default::default(),
+            deleted: false,
         }
     }
 }
@@ -70,6 +114,13 @@ pub struct Restaurant {
     pub restaurant_name: String,
     pub restaurant_address: String,
     pub phone_number: String,
+    pub deleted: bool,
+    pub categories: Vec<CategoryId>,
+    pub opening_hours: Vec<OpeningInterval>,
+    pub holidays: Vec<u32>,
+    pub utc_offset_minutes: i16,
+    pub media: RestaurantMedia,
+    pub max_open_orders: u32,
 }
 
 impl Default for Restaurant {
@@ -80,10 +131,105 @@ impl Default for Restaurant {
             restaurant_name: Default::default(),
             restaurant_address: Default::default(),
             phone_number: Default::default(),
+            deleted: false,
+            categories: Default::default(),
+            opening_hours: Default::default(),
+            holidays: Default::default(),
+            utc_offset_minutes: 0,
+            media: Default::default(),
+            max_open_orders: 0,
         }
     }
 }
 
+/// Off-chain content such as a photo or a long description, addressed by its IPFS CID
+/// `content_hash` is the SHA-256 of the content so clients can check what they fetched
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct MediaRef {
+    pub cid: String,
+    pub content_hash: [u8; 32],
+}
+
+/// Logo and storefront photo of a restaurant
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct RestaurantMedia {
+    pub logo: Option<MediaRef>,
+    pub photo: Option<MediaRef>,
+}
+
+/// Photo and long description of a food
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct FoodMedia {
+    pub photo: Option<MediaRef>,
+    pub details: Option<MediaRef>,
+}
+
+/// A weekly opening interval in restaurant local time
+/// `weekday` counts from Monday as 0, minutes count from local midnight and `close_minute` is exclusive
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct OpeningInterval {
+    pub weekday: u8,
+    pub open_minute: u16,
+    pub close_minute: u16,
+}
+
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct Category {
+    pub category_id: CategoryId,
+    pub category_name: String,
+}
+
+/// A named section of a restaurant menu, such as starters or drinks, shown in `position` order
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct MenuSection {
+    pub section_id: SectionId,
+    pub restaurant_id: RestaurantId,
+    pub section_name: String,
+    pub position: u32,
+}
+
+/// Compact view of a food as listed on a menu, read the full food with read_food
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct MenuEntry {
+    pub food_id: FoodId,
+    pub food_name: String,
+    pub food_price: Balance,
+    pub position: u32,
+}
+
+/// A menu section with its foods in display order
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct MenuGroup {
+    pub section: MenuSection,
+    pub foods: Vec<MenuEntry>,
+}
+
 #[derive(scale::Decode, scale::Encode, Debug, Clone, Eq, PartialEq)]
 #[cfg_attr(
     feature = "std",
@@ -95,6 +241,7 @@ pub struct Courier {
     pub courier_name: String,
     pub courier_address: String,
     pub phone_number: String,
+    pub deleted: bool,
 }
 
 impl Default for Courier {
@@ -105,10 +252,66 @@ impl Default for Courier {
             courier_name: Default::default(),
             courier_address: Default::default(),
             phone_number: Default::default(),
+            deleted: false,
         }
     }
 }
 
+/// Fields of a food as given to create_foods_batch and update_foods_batch
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct FoodInput {
+    pub food_name: String,
+    pub food_description: String,
+    pub food_price: Balance,
+    pub food_eta: u64,
+    pub media: FoodMedia,
+}
+
+/// A choice within an option group, such as large or extra cheese, and what it adds to the food price
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct OptionChoice {
+    pub choice_name: String,
+    pub price_delta: Balance,
+}
+
+/// A group of choices on a food, such as size or extras, of which between `min_select` and `max_select` must be chosen
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct OptionGroup {
+    pub group_name: String,
+    pub min_select: u8,
+    pub max_select: u8,
+    pub choices: Vec<OptionChoice>,
+}
+
+/// A choice picked by the customer, given as positions in the food's option groups
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct SelectedOption {
+    pub group: u8,
+    pub choice: u8,
+}
+
+/// A choice stored on an order for the kitchen
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct OrderOption {
+    pub group_name: String,
+    pub choice_name: String,
+    pub price_delta: Balance,
+}
+
 #[derive(scale::Decode, scale::Encode, Debug, Clone, Eq, PartialEq)]
 #[cfg_attr(
     feature = "std",
@@ -121,6 +324,15 @@ pub struct Food {
     pub food_description: String,
     pub food_price: Balance,
     pub food_eta: u64,
+    pub available: bool,
+    pub stock: Option<u32>,
+    pub option_groups: Vec<OptionGroup>,
+    pub section_id: SectionId,
+    pub position: u32,
+    pub media: FoodMedia,
+    pub version: u32,
+    pub dietary_flags: u32,
+    pub allergens: u32,
 }
 
 impl Default for Food {
@@ -132,6 +344,15 @@ impl Default for Food {
             food_description: Default::default(),
             food_price: Default::default(),
             food_eta: Default::default(),
+            available: true,
+            stock: None,
+            option_groups: Vec::new(),
+            section_id: 0,
+            position: 0,
+            media: Default::default(),
+            version: 0,
+            dietary_flags: 0,
+            allergens: 0,
         }
     }
 }
@@ -148,11 +369,17 @@ pub struct Order {
     pub customer_id: CustomerId,
     pub courier_id: CourierId,
     pub delivery_id: DeliveryId,
-    pub delivery_address: String,
+    pub delivery_address: EncryptedData,
     pub status: OrderStatus,
     pub timestamp: Timestamp,
     pub price: Balance,
     pub eta: u64,
+    pub options: Vec<OrderOption>,
+    pub food_name: String,
+    pub unit_price: Balance,
+    pub food_version: u32,
+    pub restaurant_name: String,
+    pub scheduled_for: Option<Timestamp>,
 }
 
 impl Default for Order {
@@ -169,10 +396,28 @@ impl Default for Order {
             timestamp: Default::default(),
             price: Default::default(),
             eta: Default::default(),
+            options: Vec::new(),
+            food_name: Default::default(),
+            unit_price: Default::default(),
+            food_version: 0,
+            restaurant_name: Default::default(),
+            scheduled_for: None,
         }
     }
 }
 
+/// Delivery details of an order, only readable by the parties to the order
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct DeliveryDetails {
+    pub order_id: OrderId,
+    pub delivery_address: EncryptedData,
+    pub phone_number: EncryptedData,
+}
+
 #[derive(scale::Decode, scale::Encode, Debug, Clone, Eq, PartialEq)]
 #[cfg_attr(
     feature = "std",
@@ -185,6 +430,11 @@ pub struct Delivery {
     pub customer_id: CustomerId,
     pub courier_id: CourierId,
     pub status: DeliveryStatus,
+    pub picked_up_at: Timestamp,
+    pub paid_at: Timestamp,
+    pub payout: Balance,
+    pub offered_to: CourierId,
+    pub offer_expires_at: Timestamp,
 }
 
 impl Default for Delivery {
@@ -196,35 +446,263 @@ impl Default for Delivery {
             customer_id: 0,
             courier_id: 0,
             status: DeliveryStatus::Waiting,
+            picked_up_at: 0,
+            paid_at: 0,
+            payout: 0,
+            offered_to: 0,
+            offer_expires_at: 0,
         }
     }
 }
 
+/// Aggregate order counters kept for the platform, each restaurant and each courier
+/// Gross merchandise value is counted when a delivery is accepted
+/// There are no platform fee or disputed counters: the order price is paid out in full to the
+/// restaurant and the courier, so the contract keeps no fee, and orders cannot be disputed yet.
+/// Orders turned down by their restaurant are counted as rejected instead
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct OrderStats {
+    pub orders_submitted: u64,
+    pub orders_confirmed: u64,
+    pub orders_delivered: u64,
+    pub orders_accepted: u64,
+    pub orders_rejected: u64,
+    pub orders_cancelled: u64,
+    pub gross_merchandise_value: Balance,
+}
+
+/// Earnings statement of a courier over a time range
+/// Tips and penalties are always 0 until the marketplace supports them, they are kept so statements won't change shape
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct CourierEarnings {
+    pub from_ts: Timestamp,
+    pub to_ts: Timestamp,
+    pub delivery_count: u64,
+    pub payouts: Balance,
+    pub tips: Balance,
+    pub penalties: Balance,
+}
+
+/// An order joined with its restaurant, courier and delivery for display, food details come from the order snapshot
+/// Courier and delivery fields are `None` until they exist
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct OrderView {
+    pub order: Order,
+    pub restaurant_name: String,
+    pub restaurant_address: String,
+    pub courier_name: Option<String>,
+    pub delivery_status: Option<DeliveryStatus>,
+}
+
+/// Public projection of a waiting delivery as shown on the open delivery board, without the customer
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct OpenDelivery {
+    pub delivery_id: DeliveryId,
+    pub order_id: OrderId,
+    pub restaurant_id: RestaurantId,
+    pub restaurant_name: String,
+    pub restaurant_address: String,
+    pub payout: Balance,
+}
+
+/// A page of a list query, `next_cursor` is `None` once the list is exhausted
+/// and `total` is the number of entries in the whole list
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<u64>,
+    pub total: u64,
+}
+
+impl<T> Page<T> {
+    /// Page of a list of `total` entries whose last read position is just before `end`
+    pub fn new(items: Vec<T>, end: u64, total: u64) -> Self {
+        Page {
+            items,
+            next_cursor: if end < total { Some(end) } else { None },
+            total,
+        }
+    }
+
+    /// Page of a list of `total` entries read newest-first, whose last read position is `end`
+    pub fn newest_first(items: Vec<T>, end: u64, total: u64) -> Self {
+        Page {
+            items,
+            next_cursor: if end > 0 { Some(end) } else { None },
+            total,
+        }
+    }
+}
+
+/// Redacted view of an item with its personal fields cleared, returned to callers
+/// who are not allowed to read them
+/// There is no default body, so every type lists what it clears
+pub trait Redact: Sized {
+    fn redacted(self) -> Self;
+}
+
+impl Redact for Customer {
+    fn redacted(mut self) -> Self {
+        self.customer_address = Default::default();
+        self.phone_number = Default::default();
+        self
+    }
+}
+
+/// A restaurant's name, address and phone number are public business details, nothing is cleared
+impl Redact for Restaurant {
+    fn redacted(self) -> Self {
+        self
+    }
+}
+
+impl Redact for Courier {
+    fn redacted(mut self) -> Self {
+        self.courier_address = Default::default();
+        self.phone_number = Default::default();
+        self
+    }
+}
+
+/// Rules that stop one account from filling more than one role on a single order
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct ConflictRules {
+    pub forbid_customer_restaurant: bool,
+    pub forbid_courier_customer: bool,
+    pub forbid_courier_restaurant: bool,
+}
+
+impl Default for ConflictRules {
+    fn default() -> Self {
+        ConflictRules {
+            forbid_customer_restaurant: true,
+            forbid_courier_customer: true,
+            forbid_courier_restaurant: true,
+        }
+    }
+}
+
+/// When a restaurant's capacity limit is checked, against orders confirmed but not yet prepared
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub enum CapacityCheck {
+    OnSubmit,
+    #[default]
+    OnConfirm,
+}
+
+/// Current load of a restaurant, `max_open_orders` is 0 when the restaurant has no limit
+/// `pending_orders` are unscheduled orders submitted but not yet confirmed, they count against the limit when it is checked on submit
+#[derive(scale::Decode, scale::Encode, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct RestaurantLoad {
+    pub open_orders: u64,
+    pub pending_orders: u64,
+    pub max_open_orders: u32,
+}
+
 #[derive(Debug)]
 #[openbrush::storage_item]
 pub struct Data {
     pub food_id: FoodId,
     pub food_data: Mapping<FoodId, Food>,
+    pub foods: Mapping<((), u64), FoodId>,
+    pub food_count: Mapping<(), u64>,
+    pub food_positions: Mapping<((), FoodId), u64>,
+    pub restaurant_foods: Mapping<(RestaurantId, u64), FoodId>,
+    pub restaurant_food_count: Mapping<RestaurantId, u64>,
+    pub restaurant_food_positions: Mapping<(RestaurantId, FoodId), u64>,
 
     pub customer_id: CustomerId, 
     pub customer_data: Mapping<AccountId, Customer>,
     pub customer_accounts: Mapping<CustomerId, AccountId>,
+    pub customer_open_orders: Mapping<CustomerId, u32>,
+    pub active_customers: Mapping<((), u64), CustomerId>,
+    pub active_customer_count: Mapping<(), u64>,
+    pub active_customer_positions: Mapping<((), CustomerId), u64>,
 
     pub restaurant_id: RestaurantId,
     pub restaurant_data: Mapping<AccountId, Restaurant>,
     pub restaurant_accounts: Mapping<RestaurantId, AccountId>,
+    pub restaurant_open_orders: Mapping<RestaurantId, u32>,
+    pub active_restaurants: Mapping<((), u64), RestaurantId>,
+    pub active_restaurant_count: Mapping<(), u64>,
+    pub active_restaurant_positions: Mapping<((), RestaurantId), u64>,
+
+    pub category_id: CategoryId,
+    pub category_data: Mapping<CategoryId, Category>,
+    pub category_restaurants: Mapping<(CategoryId, u64), RestaurantId>,
+    pub category_restaurant_count: Mapping<CategoryId, u64>,
+    pub category_restaurant_positions: Mapping<(CategoryId, RestaurantId), u64>,
+
+    pub menu_section_id: SectionId,
+    pub menu_section_data: Mapping<SectionId, MenuSection>,
+    pub restaurant_sections: Mapping<(RestaurantId, u64), SectionId>,
+    pub restaurant_section_count: Mapping<RestaurantId, u64>,
+    pub restaurant_section_positions: Mapping<(RestaurantId, SectionId), u64>,
+    pub section_foods: Mapping<(SectionId, u64), FoodId>,
+    pub section_food_count: Mapping<SectionId, u64>,
+    pub section_food_positions: Mapping<(SectionId, FoodId), u64>,
 
     pub courier_id: CourierId,
     pub courier_data: Mapping<AccountId, Courier>,
     pub courier_accounts: Mapping<CourierId, AccountId>,
+    pub courier_open_orders: Mapping<CourierId, u32>,
+    pub active_couriers: Mapping<((), u64), CourierId>,
+    pub active_courier_count: Mapping<(), u64>,
+    pub active_courier_positions: Mapping<((), CourierId), u64>,
 
     pub order_id: OrderId,
     pub order_data: Mapping<OrderId, Order>,
+    pub customer_orders: Mapping<(CustomerId, u64), OrderId>,
+    pub customer_order_count: Mapping<CustomerId, u64>,
+
+    pub courier_deliveries: Mapping<(CourierId, u64), DeliveryId>,
+    pub courier_delivery_count: Mapping<CourierId, u64>,
+    pub courier_payments: Mapping<(CourierId, u64), DeliveryId>,
+    pub courier_payment_count: Mapping<CourierId, u64>,
+    pub courier_payout_totals: Mapping<(CourierId, u64), Balance>,
+    pub restaurant_orders: Mapping<((RestaurantId, OrderStatus), u64), OrderId>,
+    pub restaurant_order_count: Mapping<(RestaurantId, OrderStatus), u64>,
+    pub restaurant_order_head: Mapping<(RestaurantId, OrderStatus), u64>,
+    pub restaurant_status_count: Mapping<(RestaurantId, OrderStatus), u64>,
+    pub restaurant_pending_orders: Mapping<RestaurantId, u64>,
 
     pub delivery_id: DeliveryId,
     pub delivery_data: Mapping<DeliveryId, Delivery>,
+    pub status_deliveries: Mapping<(DeliveryStatus, u64), DeliveryId>,
+    pub status_delivery_count: Mapping<DeliveryStatus, u64>,
+    pub status_delivery_positions: Mapping<(DeliveryStatus, DeliveryId), u64>,
 
     pub fee_rate: u8,
+    pub max_page_size: u64,
+    pub max_batch_size: u32,
+    pub schedule_lead_window: Timestamp,
+    pub courier_offer_timeout: Timestamp,
+
+    pub platform_stats: OrderStats,
+    pub restaurant_stats: Mapping<RestaurantId, OrderStats>,
+    pub courier_stats: Mapping<CourierId, OrderStats>,
+
+    pub conflict_rules: ConflictRules,
+    pub conflict_overrides: Mapping<AccountId, ()>,
+
+    pub capacity_check: CapacityCheck,
 }
 
 /// Please notice that for all Mapping<Id, Vec<>> data types defined here, 
@@ -252,28 +730,607 @@ impl Default for Data {
         Data {
             food_id: 1,
             food_data: Mapping::default(),
+            foods: Mapping::default(),
+            food_count: Mapping::default(),
+            food_positions: Mapping::default(),
+            restaurant_foods: Mapping::default(),
+            restaurant_food_count: Mapping::default(),
+            restaurant_food_positions: Mapping::default(),
 
             customer_id: 1,
             customer_data: Mapping::default(),
             customer_accounts: Mapping::default(),
+            customer_open_orders: Mapping::default(),
+            active_customers: Mapping::default(),
+            active_customer_count: Mapping::default(),
+            active_customer_positions: Mapping::default(),
 
             restaurant_id: 1,
             restaurant_data: Mapping::default(),
             restaurant_accounts: Mapping::default(),
+            restaurant_open_orders: Mapping::default(),
+            active_restaurants: Mapping::default(),
+            active_restaurant_count: Mapping::default(),
+            active_restaurant_positions: Mapping::default(),
+
+            category_id: 1,
+            category_data: Mapping::default(),
+            category_restaurants: Mapping::default(),
+            category_restaurant_count: Mapping::default(),
+            category_restaurant_positions: Mapping::default(),
+
+            menu_section_id: 1,
+            menu_section_data: Mapping::default(),
+            restaurant_sections: Mapping::default(),
+            restaurant_section_count: Mapping::default(),
+            restaurant_section_positions: Mapping::default(),
+            section_foods: Mapping::default(),
+            section_food_count: Mapping::default(),
+            section_food_positions: Mapping::default(),
 
             courier_id: 1,
             courier_data: Mapping::default(),
             courier_accounts: Mapping::default(),
+            courier_open_orders: Mapping::default(),
+            active_couriers: Mapping::default(),
+            active_courier_count: Mapping::default(),
+            active_courier_positions: Mapping::default(),
 
             order_id: 1,
             order_data: Mapping::default(),
+            customer_orders: Mapping::default(),
+            customer_order_count: Mapping::default(),
+
+            courier_deliveries: Mapping::default(),
+            courier_delivery_count: Mapping::default(),
+            courier_payments: Mapping::default(),
+            courier_payment_count: Mapping::default(),
+            courier_payout_totals: Mapping::default(),
+            restaurant_orders: Mapping::default(),
+            restaurant_order_count: Mapping::default(),
+            restaurant_order_head: Mapping::default(),
+            restaurant_status_count: Mapping::default(),
+            restaurant_pending_orders: Mapping::default(),
 
             delivery_id: 1,
             delivery_data: Mapping::default(),
+            status_deliveries: Mapping::default(),
+            status_delivery_count: Mapping::default(),
+            status_delivery_positions: Mapping::default(),
 
             fee_rate: 10,
+            max_page_size: 100,
+            max_batch_size: 20,
+            schedule_lead_window: 60 * MINUTE,
+            courier_offer_timeout: 5 * MINUTE,
+
+            platform_stats: OrderStats::default(),
+            restaurant_stats: Mapping::default(),
+            courier_stats: Mapping::default(),
+
+            conflict_rules: ConflictRules::default(),
+            conflict_overrides: Mapping::default(),
+
+            capacity_check: CapacityCheck::default(),
+        }
+    }
+}
+
+impl Data {
+    /// Validate a requested page size and cap it to the owner-configured maximum
+    pub fn page_limit(&self, limit: u64) -> Result<u64, FoodOrderError> {
+        ensure!(limit > 0, FoodOrderError::InvalidParameters);
+        Ok(min(limit, self.max_page_size))
+    }
+
+    /// Positions to read for a page starting at `cursor` in a list of `total` entries
+    pub fn page_range(&self, cursor: u64, limit: u64, total: u64) -> Result<Range<u64>, FoodOrderError> {
+        let limit = self.page_limit(limit)?;
+        ensure!(cursor <= total, FoodOrderError::InvalidParameters);
+        Ok(cursor..min(total, cursor.saturating_add(limit)))
+    }
+
+    /// Positions to read for a page of a list of `total` entries scanned newest-first, the range is read in reverse
+    /// The cursor is an exclusive upper bound, so entries appended while paging are not repeated, 0 starts from the newest entry
+    pub fn page_range_newest_first(&self, cursor: u64, limit: u64, total: u64) -> Result<Range<u64>, FoodOrderError> {
+        let limit = self.page_limit(limit)?;
+        ensure!(cursor <= total, FoodOrderError::InvalidParameters);
+        let start = if cursor == 0 { total } else { cursor };
+        Ok(start.saturating_sub(limit)..start)
+    }
+
+    /// Append a paid delivery to a courier's payment ledger, with the running payout total so earnings over any range cost two lookups
+    pub fn record_courier_payment(&mut self, courier_id: CourierId, delivery_id: DeliveryId, payout: Balance) {
+        let count = self.courier_payment_count.get(&courier_id).unwrap_or(0);
+        let payout_total = match count {
+            0 => 0,
+            count => self.courier_payout_totals.get(&(courier_id, count - 1)).unwrap(),
+        };
+        self.courier_payments.insert(&(courier_id, count), &delivery_id);
+        self.courier_payout_totals.insert(&(courier_id, count), &(payout_total + payout));
+        self.courier_payment_count.insert(&courier_id, &(count + 1));
+    }
+
+    /// Sum the payouts of a courier's deliveries paid between `from_ts` and `to_ts` inclusive
+    pub fn courier_earnings(&self, courier_id: CourierId, from_ts: Timestamp, to_ts: Timestamp) -> CourierEarnings {
+        let total = self.courier_payment_count.get(&courier_id).unwrap_or(0);
+        let paid_at = |i: u64| {
+            let delivery_id = self.courier_payments.get(&(courier_id, i)).unwrap();
+            self.delivery_data.get(&delivery_id).unwrap().paid_at
+        };
+        // Payments are appended as they happen, so the first one paid after a time is found by binary search
+        let first_paid_after = |ts: Timestamp| {
+            let (mut low, mut high) = (0, total);
+            while low < high {
+                let mid = low + (high - low) / 2;
+                if paid_at(mid) <= ts {
+                    low = mid + 1;
+                } else {
+                    high = mid;
+                }
+            }
+            low
+        };
+        // Running payout total of the payments before the given position
+        let payout_total = |end: u64| match end {
+            0 => 0,
+            end => self.courier_payout_totals.get(&(courier_id, end - 1)).unwrap(),
+        };
+
+        let start = if from_ts == 0 { 0 } else { first_paid_after(from_ts - 1) };
+        let end = first_paid_after(to_ts);
+
+        CourierEarnings {
+            from_ts,
+            to_ts,
+            delivery_count: end - start,
+            payouts: payout_total(end) - payout_total(start),
+            ..Default::default()
+        }
+    }
+
+    /// Whether a food shows up in the global catalogue and searches, that is it is available and its restaurant is not deleted
+    pub fn is_listed(&self, food: &Food) -> bool {
+        if !food.available {
+            return false;
+        }
+        match self.restaurant_accounts.get(&food.restaurant_id).and_then(|account| self.restaurant_data.get(&account)) {
+            Some(restaurant) => !restaurant.deleted,
+            None => false,
+        }
+    }
+
+    /// Whether the account is the customer or the restaurant of the order
+    fn is_order_owner(&self, order: &Order, account: &AccountId) -> bool {
+        self.customer_accounts.get(&order.customer_id).as_ref() == Some(account)
+            || self.restaurant_accounts.get(&order.restaurant_id).as_ref() == Some(account)
+    }
+
+    /// Whether the account is the courier assigned to the order, which it is from its claim on
+    fn is_order_courier(&self, order: &Order, account: &AccountId) -> bool {
+        order.courier_id != 0 && self.courier_accounts.get(&order.courier_id).as_ref() == Some(account)
+    }
+
+    /// Whether the account is the customer, the restaurant or the courier (once claimed) of the order
+    pub fn is_order_party(&self, order: &Order, account: &AccountId) -> bool {
+        self.is_order_owner(order, account) || self.is_order_courier(order, account)
+    }
+
+    /// Whether the account can read the delivery address and phone number of the order
+    /// The courier only can once it picked up the food
+    pub fn can_read_delivery_details(&self, order: &Order, account: &AccountId) -> bool {
+        if self.is_order_owner(order, account) {
+            return true;
+        }
+        self.is_order_courier(order, account) && matches!(
+            self.delivery_data.get(&order.delivery_id).map(|delivery| delivery.status),
+            Some(DeliveryStatus::PickedUp) | Some(DeliveryStatus::Accepted)
+        )
+    }
+
+    /// Append an order to its restaurant's queue of orders in the given status
+    /// Queues are append-only so they keep the order in which orders entered the status,
+    /// entries of orders that moved on to another status are skipped when read
+    pub fn enqueue_restaurant_order(&mut self, order: &Order, status: OrderStatus) {
+        let pending = status == OrderStatus::OrderSubmitted && order.scheduled_for.is_none();
+        let key = (order.restaurant_id, status);
+        let count = self.restaurant_order_count.get(&key).unwrap_or(0);
+        self.restaurant_orders.insert(&(key.clone(), count), &order.order_id);
+        self.restaurant_order_count.insert(&key, &(count + 1));
+        let status_count = self.restaurant_status_count.get(&key).unwrap_or(0);
+        self.restaurant_status_count.insert(&key, &(status_count + 1));
+
+        if pending {
+            let pending_orders = self.restaurant_pending_orders.get(&order.restaurant_id).unwrap_or(0);
+            self.restaurant_pending_orders.insert(&order.restaurant_id, &(pending_orders + 1));
+        }
+    }
+
+    /// Take an order out of its restaurant's queue of orders in its current status
+    /// The queue head moves past entries of orders no longer in the status, at most a page of them per call
+    fn dequeue_restaurant_order(&mut self, order: &Order) {
+        let key = (order.restaurant_id, order.status.clone());
+        let status_count = self.restaurant_status_count.get(&key).unwrap_or(0);
+        self.restaurant_status_count.insert(&key, &status_count.saturating_sub(1));
+
+        if order.status == OrderStatus::OrderSubmitted && order.scheduled_for.is_none() {
+            let pending_orders = self.restaurant_pending_orders.get(&order.restaurant_id).unwrap_or(0);
+            self.restaurant_pending_orders.insert(&order.restaurant_id, &pending_orders.saturating_sub(1));
+        }
+
+        let count = self.restaurant_order_count.get(&key).unwrap_or(0);
+        let mut head = self.restaurant_order_head.get(&key).unwrap_or(0);
+        let mut steps = 0;
+        while head < count && steps < self.max_page_size {
+            let order_id = self.restaurant_orders.get(&(key.clone(), head)).unwrap();
+            if order_id != order.order_id && self.order_data.get(&order_id).unwrap().status == order.status {
+                break;
+            }
+            head += 1;
+            steps += 1;
+        }
+        self.restaurant_order_head.insert(&key, &head);
+    }
+
+    /// Move the order to a new status, keeping the per-restaurant status queues in sync
+    /// Every order status transition must go through this function
+    pub fn set_order_status(&mut self, order: &mut Order, status: OrderStatus) {
+        self.dequeue_restaurant_order(order);
+        self.enqueue_restaurant_order(order, status.clone());
+
+        match status {
+            OrderStatus::OrderSubmitted => (),
+            OrderStatus::OrderConfirmed => self.update_stats(order, |stats| stats.orders_confirmed += 1),
+            OrderStatus::FoodPrepared => (),
+            OrderStatus::FoodDelivered => self.update_stats(order, |stats| stats.orders_delivered += 1),
+            OrderStatus::DeliveryAccepted => {
+                let price = order.price;
+                self.update_stats(order, |stats| {
+                    stats.orders_accepted += 1;
+                    stats.gross_merchandise_value += price;
+                });
+            }
+            OrderStatus::OrderRejected => self.update_stats(order, |stats| stats.orders_rejected += 1),
+            OrderStatus::OrderCancelled => self.update_stats(order, |stats| stats.orders_cancelled += 1),
+        }
+
+        order.status = status;
+    }
+
+    /// Apply an update to the platform counters and to those of the restaurant and courier (once assigned) of the order
+    pub fn update_stats(&mut self, order: &Order, update: impl Fn(&mut OrderStats)) {
+        update(&mut self.platform_stats);
+
+        let mut restaurant_stats = self.restaurant_stats.get(&order.restaurant_id).unwrap_or_default();
+        update(&mut restaurant_stats);
+        self.restaurant_stats.insert(&order.restaurant_id, &restaurant_stats);
+
+        if order.courier_id != 0 {
+            let mut courier_stats = self.courier_stats.get(&order.courier_id).unwrap_or_default();
+            update(&mut courier_stats);
+            self.courier_stats.insert(&order.courier_id, &courier_stats);
+        }
+    }
+
+    /// Move the delivery to a new status, keeping the per-status delivery index in sync
+    /// Every delivery status transition must go through this function
+    pub fn set_delivery_status(&mut self, delivery: &mut Delivery, status: DeliveryStatus) {
+        index_remove!(self, status_deliveries, status_delivery_count, status_delivery_positions, delivery.status.clone(), delivery.delivery_id);
+        index_insert!(self, status_deliveries, status_delivery_count, status_delivery_positions, status.clone(), delivery.delivery_id);
+        delivery.status = status;
+    }
+
+    /// Whether any courier may take a delivery, either waiting on the open board or offered to a courier who let the offer expire
+    pub fn is_up_for_grabs(&self, delivery: &Delivery, now: Timestamp) -> bool {
+        delivery.status == DeliveryStatus::Waiting || (delivery.status == DeliveryStatus::Offered && now > delivery.offer_expires_at)
+    }
+
+    /// Put an offered delivery back on the open board
+    pub fn withdraw_offer(&mut self, delivery: &mut Delivery) {
+        self.set_delivery_status(delivery, DeliveryStatus::Waiting);
+        delivery.offered_to = 0;
+        delivery.offer_expires_at = 0;
+    }
+
+    /// Check the conflict-of-interest rules between a courier and the parties to a delivery
+    pub fn ensure_no_courier_conflict(&self, courier_account: &AccountId, delivery: &Delivery) -> Result<(), FoodOrderError> {
+        if self.conflict_overrides.contains(courier_account) {
+            return Ok(());
+        }
+        let customer_account = self.customer_accounts.get(&delivery.customer_id).unwrap();
+        let restaurant_account = self.restaurant_accounts.get(&delivery.restaurant_id).unwrap();
+        ensure!(!(self.conflict_rules.forbid_courier_customer && *courier_account == customer_account), FoodOrderError::ConflictOfInterest);
+        ensure!(!(self.conflict_rules.forbid_courier_restaurant && *courier_account == restaurant_account), FoodOrderError::ConflictOfInterest);
+        Ok(())
+    }
+
+    /// Reserve a delivery for a courier, recording the courier on the order
+    pub fn assign_delivery(&mut self, delivery: &mut Delivery, courier_id: CourierId) {
+        self.set_delivery_status(delivery, DeliveryStatus::Claimed);
+        delivery.courier_id = courier_id;
+        delivery.offered_to = 0;
+        delivery.offer_expires_at = 0;
+        self.delivery_data.insert(&delivery.delivery_id, delivery);
+
+        let mut order = self.order_data.get(&delivery.order_id).unwrap();
+        order.courier_id = courier_id;
+        self.order_data.insert(&order.order_id, &order);
+
+        let courier_open_orders = self.courier_open_orders.get(&courier_id).unwrap_or(0);
+        self.courier_open_orders.insert(&courier_id, &(courier_open_orders + 1));
+    }
+
+    /// Put a claimed delivery back on the open board, undoing assign_delivery
+    pub fn release_delivery(&mut self, delivery: &mut Delivery) {
+        let courier_id = delivery.courier_id;
+        self.set_delivery_status(delivery, DeliveryStatus::Waiting);
+        delivery.courier_id = 0;
+        self.delivery_data.insert(&delivery.delivery_id, delivery);
+
+        let mut order = self.order_data.get(&delivery.order_id).unwrap();
+        order.courier_id = 0;
+        self.order_data.insert(&order.order_id, &order);
+
+        let courier_open_orders = self.courier_open_orders.get(&courier_id).unwrap_or(0);
+        self.courier_open_orders.insert(&courier_id, &courier_open_orders.saturating_sub(1));
+    }
+
+    /// Load of a restaurant, the number of its orders confirmed but not yet prepared and of its unscheduled orders awaiting confirmation
+    pub fn restaurant_load(&self, restaurant: &Restaurant) -> RestaurantLoad {
+        RestaurantLoad {
+            open_orders: self.restaurant_status_count.get(&(restaurant.restaurant_id, OrderStatus::OrderConfirmed)).unwrap_or(0),
+            pending_orders: self.restaurant_pending_orders.get(&restaurant.restaurant_id).unwrap_or(0),
+            max_open_orders: restaurant.max_open_orders,
         }
     }
+
+    /// Number of orders held against a restaurant's capacity limit
+    /// When the limit is checked on submit, unscheduled orders awaiting confirmation already hold a place
+    fn capacity_taken(&self, load: &RestaurantLoad) -> u64 {
+        match self.capacity_check {
+            CapacityCheck::OnSubmit => load.open_orders + load.pending_orders,
+            CapacityCheck::OnConfirm => load.open_orders,
+        }
+    }
+
+    /// Refuse a new order when the limit is checked on submit and the restaurant is at its capacity limit
+    /// A scheduled order is only checked when it is confirmed in its lead window, its load is not known ahead
+    pub fn ensure_capacity_on_submit(&self, restaurant: &Restaurant, scheduled_for: Option<Timestamp>) -> Result<(), FoodOrderError> {
+        if restaurant.max_open_orders == 0 || self.capacity_check != CapacityCheck::OnSubmit || scheduled_for.is_some() {
+            return Ok(());
+        }
+        let load = self.restaurant_load(restaurant);
+        ensure!(self.capacity_taken(&load) < load.max_open_orders as u64, FoodOrderError::RestaurantAtCapacity);
+        Ok(())
+    }
+
+    /// Refuse to confirm an order when the restaurant is at its capacity limit, whichever step the limit is checked at
+    /// An order that already holds a place since it was submitted is not counted twice
+    pub fn ensure_capacity_on_confirm(&self, restaurant: &Restaurant, order: &Order) -> Result<(), FoodOrderError> {
+        if restaurant.max_open_orders == 0 {
+            return Ok(());
+        }
+        let load = self.restaurant_load(restaurant);
+        let mut taken = self.capacity_taken(&load);
+        if self.capacity_check == CapacityCheck::OnSubmit && order.status == OrderStatus::OrderSubmitted && order.scheduled_for.is_none() {
+            taken = taken.saturating_sub(1);
+        }
+        ensure!(taken < load.max_open_orders as u64, FoodOrderError::RestaurantAtCapacity);
+        Ok(())
+    }
+
+    /// Whether a restaurant takes orders at the given time
+    /// A restaurant without opening hours is always open except on its holidays, which are local days since 1970-01-01
+    pub fn is_open(&self, restaurant: &Restaurant, now: Timestamp) -> bool {
+        let offset = restaurant.utc_offset_minutes as i64 * MINUTE as i64;
+        let local_minutes = (now as i64 + offset).max(0) as u64 / MINUTE;
+
+        let day = local_minutes / MINUTES_PER_DAY as u64;
+        if restaurant.holidays.contains(&(day as u32)) {
+            return false;
+        }
+        if restaurant.opening_hours.is_empty() {
+            return true;
+        }
+
+        // 1970-01-01 was a Thursday
+        let weekday = ((day + 3) % 7) as u8;
+        let minute = (local_minutes % MINUTES_PER_DAY as u64) as u16;
+        restaurant.opening_hours.iter().any(|interval| {
+            interval.weekday == weekday && interval.open_minute <= minute && minute < interval.close_minute
+        })
+    }
+
+    /// Check the fields of a new food
+    pub fn validate_food_input(&self, input: &FoodInput) -> Result<(), FoodOrderError> {
+        ensure!(input.food_name.len() > 0, FoodOrderError::InvalidNameLength);
+        ensure!(input.food_description.len() > 0, FoodOrderError::InvalidDescriptionLength);
+        ensure!(input.food_price > 0, FoodOrderError::InvalidParameters);
+        self.validate_food_media(&input.media)
+    }
+
+    /// Check the fields of an update to a food of the given restaurant, which also needs a non-zero eta
+    pub fn validate_food_update(&self, restaurant_id: RestaurantId, food_id: FoodId, input: &FoodInput) -> Result<(), FoodOrderError> {
+        ensure!(self.food_data.contains(&food_id), FoodOrderError::FoodNotExist);
+        self.validate_food_input(input)?;
+        ensure!(input.food_eta > 0, FoodOrderError::InvalidParameters);
+        ensure!(self.food_data.get(&food_id).unwrap().restaurant_id == restaurant_id, FoodOrderError::CallerIsNotFoodOwner);
+        Ok(())
+    }
+
+    /// Add a food to the menu of a restaurant, the input must have been validated
+    pub fn insert_food(&mut self, restaurant_id: RestaurantId, input: FoodInput) -> FoodId {
+        let food_id = self.food_id;
+        let food = Food {
+            food_id,
+            food_name: input.food_name,
+            restaurant_id,
+            food_description: input.food_description,
+            food_price: input.food_price,
+            food_eta: input.food_eta,
+            media: input.media,
+            version: 1,
+            ..Default::default()
+        };
+        self.food_id += 1;
+        self.food_data.insert(&food_id, &food);
+
+        index_insert!(self, foods, food_count, food_positions, (), food_id);
+        index_insert!(self, restaurant_foods, restaurant_food_count, restaurant_food_positions, restaurant_id, food_id);
+
+        food_id
+    }
+
+    /// Apply an update to a food and bump its version, the input must have been validated
+    pub fn apply_food_update(&mut self, food_id: FoodId, input: FoodInput) {
+        let mut food = self.food_data.get(&food_id).unwrap();
+        food.food_name = input.food_name;
+        food.food_description = input.food_description;
+        food.food_price = input.food_price;
+        food.food_eta = input.food_eta;
+        food.media = input.media;
+        food.version += 1;
+
+        self.food_data.insert(&food_id, &food);
+    }
+
+    /// Check the number of entries of a batch message
+    pub fn validate_batch_size(&self, size: usize) -> Result<(), FoodOrderError> {
+        ensure!(size > 0, FoodOrderError::InvalidParameters);
+        ensure!(size <= self.max_batch_size as usize, FoodOrderError::BatchTooLarge);
+        Ok(())
+    }
+
+    /// Check the format of a media reference, a CIDv0 in base58 or a CIDv1 in base32 with a non-zero content hash
+    pub fn validate_media_ref(&self, media: &Option<MediaRef>) -> Result<(), FoodOrderError> {
+        let media = match media {
+            Some(media) => media,
+            None => return Ok(()),
+        };
+
+        let cid = media.cid.as_bytes();
+        let valid_cid = if cid.starts_with(b"Qm") {
+            cid.len() == 46 && cid.iter().all(|c| c.is_ascii_alphanumeric() && !b"0OIl".contains(c))
+        } else if cid.starts_with(b"b") {
+            cid.len() > 1 && cid.len() <= MAX_CID_LENGTH && cid.iter().all(|c| c.is_ascii_lowercase() || (b'2'..=b'7').contains(c))
+        } else {
+            false
+        };
+        ensure!(valid_cid, FoodOrderError::InvalidMedia);
+        ensure!(media.content_hash != [0u8; 32], FoodOrderError::InvalidMedia);
+
+        Ok(())
+    }
+
+    /// Check every media reference of a food
+    pub fn validate_food_media(&self, media: &FoodMedia) -> Result<(), FoodOrderError> {
+        self.validate_media_ref(&media.photo)?;
+        self.validate_media_ref(&media.details)
+    }
+
+    /// Check every media reference of a restaurant
+    pub fn validate_restaurant_media(&self, media: &RestaurantMedia) -> Result<(), FoodOrderError> {
+        self.validate_media_ref(&media.logo)?;
+        self.validate_media_ref(&media.photo)
+    }
+
+    /// Check option groups set by a restaurant on a food
+    /// The price deltas of all choices together must fit in a balance, so no selection can overflow the order price
+    pub fn validate_option_groups(&self, option_groups: &[OptionGroup]) -> Result<(), FoodOrderError> {
+        ensure!(option_groups.len() <= MAX_OPTION_GROUPS, FoodOrderError::InvalidOptions);
+        let mut price_deltas: Balance = 0;
+        for group in option_groups {
+            ensure!(group.group_name.len() > 0, FoodOrderError::InvalidNameLength);
+            ensure!(group.choices.len() > 0 && group.choices.len() <= MAX_OPTION_CHOICES, FoodOrderError::InvalidOptions);
+            ensure!(group.min_select <= group.max_select, FoodOrderError::InvalidOptions);
+            ensure!(group.max_select as usize <= group.choices.len(), FoodOrderError::InvalidOptions);
+            for choice in &group.choices {
+                ensure!(choice.choice_name.len() > 0, FoodOrderError::InvalidNameLength);
+                price_deltas = price_deltas.checked_add(choice.price_delta).ok_or(FoodOrderError::InvalidOptions)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the options picked by a customer against the food's option groups
+    /// Returns the food price with the price deltas of the picked choices added, and the choices to store on the order
+    pub fn price_with_options(&self, food: &Food, selected: &[SelectedOption]) -> Result<(Balance, Vec<OrderOption>), FoodOrderError> {
+        let mut counts = vec![0u8; food.option_groups.len()];
+
+        let mut price = food.food_price;
+        let mut options: Vec<OrderOption> = Vec::new();
+        for (i, selection) in selected.iter().enumerate() {
+            ensure!(!selected[..i].contains(selection), FoodOrderError::InvalidOptions);
+
+            let group = food.option_groups.get(selection.group as usize).ok_or(FoodOrderError::InvalidOptions)?;
+            let choice = group.choices.get(selection.choice as usize).ok_or(FoodOrderError::InvalidOptions)?;
+
+            counts[selection.group as usize] += 1;
+            price = price.checked_add(choice.price_delta).ok_or(FoodOrderError::InvalidOptions)?;
+            options.push(OrderOption {
+                group_name: group.group_name.clone(),
+                choice_name: choice.choice_name.clone(),
+                price_delta: choice.price_delta,
+            });
+        }
+
+        for (group, count) in food.option_groups.iter().zip(counts) {
+            ensure!(count >= group.min_select && count <= group.max_select, FoodOrderError::InvalidOptions);
+        }
+
+        Ok((price, options))
+    }
+
+    /// Release what an order that will not be fulfilled holds: its unit of stock and the open-order counters of its parties
+    /// The caller refunds the customer
+    pub fn release_order(&mut self, order: &Order) {
+        if let Some(mut food) = self.food_data.get(&order.food_id) {
+            if let Some(stock) = food.stock {
+                food.stock = Some(stock.saturating_add(1));
+                self.food_data.insert(&order.food_id, &food);
+            }
+        }
+
+        let customer_open_orders = self.customer_open_orders.get(&order.customer_id).unwrap_or(0);
+        self.customer_open_orders.insert(&order.customer_id, &customer_open_orders.saturating_sub(1));
+        let restaurant_open_orders = self.restaurant_open_orders.get(&order.restaurant_id).unwrap_or(0);
+        self.restaurant_open_orders.insert(&order.restaurant_id, &restaurant_open_orders.saturating_sub(1));
+    }
+
+    /// Whether a scheduled order is within its lead window, when the restaurant may confirm it and the customer can no longer cancel it
+    /// Orders without a requested time are always within it
+    pub fn in_lead_window(&self, order: &Order, now: Timestamp) -> bool {
+        match order.scheduled_for {
+            Some(scheduled_for) => now >= scheduled_for.saturating_sub(self.schedule_lead_window) && now <= scheduled_for,
+            None => true,
+        }
+    }
+
+    /// Public projection of a waiting delivery
+    pub fn open_delivery(&self, delivery: &Delivery) -> OpenDelivery {
+        let order = self.order_data.get(&delivery.order_id).unwrap();
+        let restaurant_account = self.restaurant_accounts.get(&delivery.restaurant_id).unwrap();
+        let restaurant = self.restaurant_data.get(&restaurant_account).unwrap();
+
+        OpenDelivery {
+            delivery_id: delivery.delivery_id,
+            order_id: delivery.order_id,
+            restaurant_id: delivery.restaurant_id,
+            restaurant_name: restaurant.restaurant_name,
+            restaurant_address: restaurant.restaurant_address,
+            payout: self.courier_payout(&order),
+        }
+    }
+
+    /// Amount paid to the restaurant of an order once its food is prepared
+    pub fn restaurant_payout(&self, order: &Order) -> Balance {
+        order.price - order.price / (self.fee_rate as u128)
+    }
+
+    /// Amount paid to the courier of an order once its delivery is accepted
+    pub fn courier_payout(&self, order: &Order) -> Balance {
+        order.price / (self.fee_rate as u128)
+    }
 }
 
 #[derive(scale::Decode, scale::Encode, PartialEq, Eq, Debug)]
@@ -311,6 +1368,35 @@ pub enum FoodOrderError {
     NotTransfered,
     InvalidParameters,
     InvalidRate,
+    HasOpenOrders,
+    RestaurantNotActive,
+    ConflictOfInterest,
+    CallerIsNotOrderParty,
+    OrderStatusNotSubmitted,
+    CategoryNotExist,
+    InvalidCategories,
+    FoodNotAvailable,
+    FoodSoldOut,
+    InvalidOptions,
+    RestaurantClosed,
+    MenuSectionNotExist,
+    MenuSectionNotEmpty,
+    MenuSectionFull,
+    CallerIsNotSectionOwner,
+    InvalidMedia,
+    BatchTooLarge,
+    RestaurantAtCapacity,
+    InvalidScheduledTime,
+    OutsideLeadWindow,
+    OrderNotCancellable,
+    DeliveryStatusNotOffered,
+    DeliveryStatusNotClaimed,
+    DeliveryStatusNotHandedOver,
+    CallerIsNotDeliveryCourier,
+    CallerIsNotOfferedCourier,
+    OfferExpired,
+    OfferNotExpired,
+    TooManyMenuSections,
 }
 
 impl From<OwnableError> for FoodOrderError {
@@ -323,4 +1409,472 @@ impl From<OwnableError> for FoodOrderError {
 //     fn from(error: AccessControlError) -> Self {
 //         FoodOrderError::AccessControlError(error)
 //     }
-// }
\ No newline at end of file
+// }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn choice(choice_name: &str, price_delta: Balance) -> OptionChoice {
+        OptionChoice { choice_name: String::from(choice_name), price_delta }
+    }
+
+    fn pick(group: u8, choice: u8) -> SelectedOption {
+        SelectedOption { group, choice }
+    }
+
+    /// A food with a required size and up to two extras
+    fn food_with_options() -> Food {
+        Food {
+            food_price: 1000,
+            option_groups: vec![
+                OptionGroup {
+                    group_name: String::from("Size"),
+                    min_select: 1,
+                    max_select: 1,
+                    choices: vec![choice("Small", 0), choice("Large", 300)],
+                },
+                OptionGroup {
+                    group_name: String::from("Extras"),
+                    min_select: 0,
+                    max_select: 2,
+                    choices: vec![choice("Cheese", 100), choice("Bacon", 200), choice("Egg", 50)],
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn price_with_options_adds_price_deltas() {
+        let data = Data::default();
+
+        let (price, options) = data.price_with_options(&food_with_options(), &[pick(0, 1), pick(1, 0), pick(1, 1)]).unwrap();
+        assert_eq!(price, 1600);
+        assert_eq!(options.len(), 3);
+        assert_eq!(options[0].group_name, "Size");
+        assert_eq!(options[0].choice_name, "Large");
+        assert_eq!(options[2].price_delta, 200);
+
+        let (price, options) = data.price_with_options(&food_with_options(), &[pick(0, 0)]).unwrap();
+        assert_eq!(price, 1000);
+        assert_eq!(options.len(), 1);
+    }
+
+    #[test]
+    fn price_with_options_without_option_groups() {
+        let data = Data::default();
+        let food = Food { food_price: 1000, ..Default::default() };
+
+        assert_eq!(data.price_with_options(&food, &[]), Ok((1000, Vec::new())));
+        assert_eq!(data.price_with_options(&food, &[pick(0, 0)]), Err(FoodOrderError::InvalidOptions));
+    }
+
+    #[test]
+    fn price_with_options_enforces_min_and_max_select() {
+        let data = Data::default();
+        let food = food_with_options();
+
+        // No size picked
+        assert_eq!(data.price_with_options(&food, &[pick(1, 0)]), Err(FoodOrderError::InvalidOptions));
+        // Two sizes picked
+        assert_eq!(data.price_with_options(&food, &[pick(0, 0), pick(0, 1)]), Err(FoodOrderError::InvalidOptions));
+        // Three extras picked
+        assert_eq!(
+            data.price_with_options(&food, &[pick(0, 0), pick(1, 0), pick(1, 1), pick(1, 2)]),
+            Err(FoodOrderError::InvalidOptions)
+        );
+    }
+
+    #[test]
+    fn price_with_options_rejects_duplicate_and_unknown_choices() {
+        let data = Data::default();
+        let food = food_with_options();
+
+        assert_eq!(data.price_with_options(&food, &[pick(0, 0), pick(1, 0), pick(1, 0)]), Err(FoodOrderError::InvalidOptions));
+        assert_eq!(data.price_with_options(&food, &[pick(0, 2)]), Err(FoodOrderError::InvalidOptions));
+        assert_eq!(data.price_with_options(&food, &[pick(0, 0), pick(2, 0)]), Err(FoodOrderError::InvalidOptions));
+    }
+
+    /// Time `hour:minute` UTC on the given day since 1970-01-01, a Thursday
+    fn at(day: u64, hour: u64, minute: u64) -> Timestamp {
+        (day * MINUTES_PER_DAY as u64 + hour * 60 + minute) * MINUTE
+    }
+
+    fn restaurant_with_hours(opening_hours: Vec<OpeningInterval>, utc_offset_minutes: i16, holidays: Vec<u32>) -> Restaurant {
+        Restaurant { opening_hours, utc_offset_minutes, holidays, ..Default::default() }
+    }
+
+    fn interval(weekday: u8, open_minute: u16, close_minute: u16) -> OpeningInterval {
+        OpeningInterval { weekday, open_minute, close_minute }
+    }
+
+    #[test]
+    fn is_open_within_interval() {
+        let data = Data::default();
+        // Thursdays 09:00 to 17:00
+        let restaurant = restaurant_with_hours(vec![interval(3, 9 * 60, 17 * 60)], 0, Vec::new());
+
+        assert!(!data.is_open(&restaurant, at(0, 8, 59)));
+        assert!(data.is_open(&restaurant, at(0, 9, 0)));
+        assert!(data.is_open(&restaurant, at(0, 16, 59)));
+        assert!(!data.is_open(&restaurant, at(0, 17, 0)));
+        // Next Thursday, but not the Friday in between
+        assert!(!data.is_open(&restaurant, at(1, 12, 0)));
+        assert!(data.is_open(&restaurant, at(7, 12, 0)));
+    }
+
+    #[test]
+    fn is_open_across_weekday_boundary() {
+        let data = Data::default();
+        // Sundays 22:00 to midnight and Mondays midnight to 02:00
+        let restaurant = restaurant_with_hours(vec![interval(6, 22 * 60, 24 * 60), interval(0, 0, 2 * 60)], 0, Vec::new());
+
+        assert!(!data.is_open(&restaurant, at(3, 21, 59)));
+        assert!(data.is_open(&restaurant, at(3, 23, 59)));
+        assert!(data.is_open(&restaurant, at(4, 0, 0)));
+        assert!(data.is_open(&restaurant, at(4, 1, 59)));
+        assert!(!data.is_open(&restaurant, at(4, 2, 0)));
+        // Saturday night is closed
+        assert!(!data.is_open(&restaurant, at(2, 23, 0)));
+    }
+
+    #[test]
+    fn is_open_with_utc_offset() {
+        let data = Data::default();
+        // Sundays 23:00 to midnight local time
+        let restaurant = restaurant_with_hours(vec![interval(6, 23 * 60, 24 * 60)], -60, Vec::new());
+
+        // Monday 00:30 UTC is Sunday 23:30 at UTC-1
+        assert!(data.is_open(&restaurant, at(4, 0, 30)));
+        assert!(!data.is_open(&restaurant, at(3, 23, 30)));
+
+        // Sunday 22:30 UTC is Sunday 23:30 at UTC+1, and Sunday 23:30 UTC is already Monday
+        let restaurant = restaurant_with_hours(vec![interval(6, 23 * 60, 24 * 60)], 60, Vec::new());
+        assert!(data.is_open(&restaurant, at(3, 22, 30)));
+        assert!(!data.is_open(&restaurant, at(3, 23, 30)));
+    }
+
+    #[test]
+    fn is_open_on_holidays() {
+        let data = Data::default();
+
+        // Without opening hours a restaurant is open every day but its holidays
+        let restaurant = restaurant_with_hours(Vec::new(), 0, vec![5]);
+        assert!(data.is_open(&restaurant, at(4, 23, 59)));
+        assert!(!data.is_open(&restaurant, at(5, 0, 0)));
+        assert!(!data.is_open(&restaurant, at(5, 23, 59)));
+        assert!(data.is_open(&restaurant, at(6, 0, 0)));
+
+        // Holidays are local days, at UTC-1 day 5 starts an hour later
+        let restaurant = restaurant_with_hours(Vec::new(), -60, vec![5]);
+        assert!(data.is_open(&restaurant, at(5, 0, 30)));
+        assert!(!data.is_open(&restaurant, at(5, 1, 0)));
+
+        // Holidays close a restaurant during its opening hours
+        let restaurant = restaurant_with_hours(vec![interval(3, 0, 24 * 60)], 0, vec![7]);
+        assert!(data.is_open(&restaurant, at(0, 12, 0)));
+        assert!(!data.is_open(&restaurant, at(7, 12, 0)));
+    }
+
+    fn media(cid: &str) -> Option<MediaRef> {
+        Some(MediaRef { cid: String::from(cid), content_hash: [1u8; 32] })
+    }
+
+    const CID_V0: &str = "QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG";
+    const CID_V1: &str = "bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi";
+
+    #[test]
+    fn validate_media_ref_accepts_cids() {
+        let data = Data::default();
+
+        assert_eq!(data.validate_media_ref(&None), Ok(()));
+        assert_eq!(data.validate_media_ref(&media(CID_V0)), Ok(()));
+        assert_eq!(data.validate_media_ref(&media(CID_V1)), Ok(()));
+    }
+
+    #[test]
+    fn validate_media_ref_rejects_malformed_cid_v0() {
+        let data = Data::default();
+
+        // One character short
+        assert_eq!(data.validate_media_ref(&media(&CID_V0[..45])), Err(FoodOrderError::InvalidMedia));
+        // Characters outside the base58 alphabet
+        for c in ["0", "O", "I", "l", "+"] {
+            let cid = String::from(&CID_V0[..45]) + c;
+            assert_eq!(data.validate_media_ref(&media(&cid)), Err(FoodOrderError::InvalidMedia));
+        }
+    }
+
+    #[test]
+    fn validate_media_ref_rejects_malformed_cid_v1() {
+        let data = Data::default();
+
+        assert_eq!(data.validate_media_ref(&media("b")), Err(FoodOrderError::InvalidMedia));
+        assert_eq!(data.validate_media_ref(&media(&CID_V1.to_uppercase())), Err(FoodOrderError::InvalidMedia));
+        // Characters outside the base32 alphabet
+        for c in ["1", "8", "0", "="] {
+            let cid = String::from(CID_V1) + c;
+            assert_eq!(data.validate_media_ref(&media(&cid)), Err(FoodOrderError::InvalidMedia));
+        }
+        // Longer than the maximum length
+        let cid = String::from("b") + &"a".repeat(MAX_CID_LENGTH);
+        assert_eq!(data.validate_media_ref(&media(&cid)), Err(FoodOrderError::InvalidMedia));
+        assert_eq!(data.validate_media_ref(&media(&cid[..MAX_CID_LENGTH])), Ok(()));
+    }
+
+    #[test]
+    fn validate_media_ref_rejects_other_cids_and_empty_hashes() {
+        let data = Data::default();
+
+        assert_eq!(data.validate_media_ref(&media("")), Err(FoodOrderError::InvalidMedia));
+        assert_eq!(data.validate_media_ref(&media("zdj7WWeQ43G6JJvLWQWZpyHuAMq6uYWRjkBXFad11vE2LHhQ7")), Err(FoodOrderError::InvalidMedia));
+        assert_eq!(
+            data.validate_media_ref(&Some(MediaRef { cid: String::from(CID_V0), content_hash: [0u8; 32] })),
+            Err(FoodOrderError::InvalidMedia)
+        );
+    }
+
+    #[test]
+    fn redacted_courier_has_no_personal_fields() {
+        let courier = Courier {
+            courier_id: 1,
+            courier_name: String::from("Courier A"),
+            courier_address: String::from("Home address"),
+            phone_number: String::from("0123456789"),
+            ..Default::default()
+        };
+
+        let redacted = courier.clone().redacted();
+        assert_eq!(redacted.courier_address, "");
+        assert_eq!(redacted.phone_number, "");
+        assert_eq!(redacted.courier_name, courier.courier_name);
+        assert_eq!(redacted.courier_id, courier.courier_id);
+    }
+
+    /// Data with courier 1 paid 100, 200 and 300 for deliveries paid at times 10, 20 and 20
+    fn data_with_payments() -> Data {
+        let mut data = Data::default();
+        for (delivery_id, paid_at, payout) in [(1, 10, 100), (2, 20, 200), (3, 20, 300)] {
+            data.delivery_data.insert(delivery_id, &Delivery { delivery_id, courier_id: 1, paid_at, payout, ..Default::default() });
+            data.record_courier_payment(1, delivery_id, payout);
+        }
+        data
+    }
+
+    #[ink::test]
+    fn courier_earnings_range_is_inclusive() {
+        let data = data_with_payments();
+
+        let earnings = data.courier_earnings(1, 10, 20);
+        assert_eq!((earnings.delivery_count, earnings.payouts), (3, 600));
+        assert_eq!((earnings.from_ts, earnings.to_ts), (10, 20));
+
+        let earnings = data.courier_earnings(1, 10, 10);
+        assert_eq!((earnings.delivery_count, earnings.payouts), (1, 100));
+
+        let earnings = data.courier_earnings(1, 20, 20);
+        assert_eq!((earnings.delivery_count, earnings.payouts), (2, 500));
+
+        let earnings = data.courier_earnings(1, 11, 19);
+        assert_eq!((earnings.delivery_count, earnings.payouts), (0, 0));
+    }
+
+    #[ink::test]
+    fn courier_earnings_from_the_start() {
+        let data = data_with_payments();
+
+        let earnings = data.courier_earnings(1, 0, 15);
+        assert_eq!((earnings.delivery_count, earnings.payouts), (1, 100));
+
+        let earnings = data.courier_earnings(1, 0, Timestamp::MAX);
+        assert_eq!((earnings.delivery_count, earnings.payouts), (3, 600));
+        assert_eq!((earnings.tips, earnings.penalties), (0, 0));
+    }
+
+    #[ink::test]
+    fn courier_earnings_without_payments() {
+        let data = data_with_payments();
+
+        // Before the first and after the last payment
+        let earnings = data.courier_earnings(1, 0, 9);
+        assert_eq!((earnings.delivery_count, earnings.payouts), (0, 0));
+        let earnings = data.courier_earnings(1, 21, 1000);
+        assert_eq!((earnings.delivery_count, earnings.payouts), (0, 0));
+
+        // A courier that was never paid
+        let earnings = data.courier_earnings(2, 0, Timestamp::MAX);
+        assert_eq!((earnings.delivery_count, earnings.payouts), (0, 0));
+    }
+
+    #[test]
+    fn page_range_newest_first_uses_cursor_as_upper_bound() {
+        let data = Data::default();
+
+        assert_eq!(data.page_range_newest_first(0, 2, 5), Ok(3..5));
+        assert_eq!(data.page_range_newest_first(3, 2, 5), Ok(1..3));
+        assert_eq!(data.page_range_newest_first(1, 2, 5), Ok(0..1));
+        assert_eq!(data.page_range_newest_first(0, 2, 0), Ok(0..0));
+        assert_eq!(data.page_range_newest_first(6, 2, 5), Err(FoodOrderError::InvalidParameters));
+        assert_eq!(data.page_range_newest_first(0, 0, 5), Err(FoodOrderError::InvalidParameters));
+
+        assert_eq!(Page::newest_first(vec![5u64, 4], 3, 5).next_cursor, Some(3));
+        assert_eq!(Page::newest_first(vec![1u64], 0, 5).next_cursor, None);
+    }
+
+    #[ink::test]
+    fn release_order_restores_stock_without_overflow() {
+        let mut data = Data::default();
+        let order = Order { order_id: 1, food_id: 1, ..Default::default() };
+
+        data.food_data.insert(1, &Food { food_id: 1, stock: Some(2), ..Default::default() });
+        data.release_order(&order);
+        assert_eq!(data.food_data.get(1).unwrap().stock, Some(3));
+
+        // The restaurant raised the stock to the maximum after the order took its unit
+        data.food_data.insert(1, &Food { food_id: 1, stock: Some(u32::MAX), ..Default::default() });
+        data.release_order(&order);
+        assert_eq!(data.food_data.get(1).unwrap().stock, Some(u32::MAX));
+
+        // Foods without a stock limit stay unlimited
+        data.food_data.insert(1, &Food { food_id: 1, stock: None, ..Default::default() });
+        data.release_order(&order);
+        assert_eq!(data.food_data.get(1).unwrap().stock, None);
+    }
+
+    #[test]
+    fn price_deltas_cannot_overflow_the_price() {
+        let data = Data::default();
+        let mut food = food_with_options();
+        food.option_groups[1].choices[0].price_delta = Balance::MAX - 1000;
+
+        assert_eq!(data.price_with_options(&food, &[pick(0, 0), pick(1, 0)]), Ok((Balance::MAX, vec![
+            OrderOption { group_name: String::from("Size"), choice_name: String::from("Small"), price_delta: 0 },
+            OrderOption { group_name: String::from("Extras"), choice_name: String::from("Cheese"), price_delta: Balance::MAX - 1000 },
+        ])));
+        assert_eq!(data.price_with_options(&food, &[pick(0, 1), pick(1, 0)]), Err(FoodOrderError::InvalidOptions));
+
+        // All price deltas together are 550 plus the cheese delta
+        food.option_groups[1].choices[0].price_delta = Balance::MAX - 550;
+        assert_eq!(data.validate_option_groups(&food.option_groups), Ok(()));
+        food.option_groups[1].choices[0].price_delta = Balance::MAX - 549;
+        assert_eq!(data.validate_option_groups(&food.option_groups), Err(FoodOrderError::InvalidOptions));
+    }
+
+    /// Submit an order to restaurant 1, going through the capacity check as `submit_order` does
+    fn submit_capacity_order(data: &mut Data, restaurant: &Restaurant, order_id: OrderId) -> Result<Order, FoodOrderError> {
+        submit_scheduled_capacity_order(data, restaurant, order_id, None)
+    }
+
+    fn submit_scheduled_capacity_order(data: &mut Data, restaurant: &Restaurant, order_id: OrderId, scheduled_for: Option<Timestamp>) -> Result<Order, FoodOrderError> {
+        data.ensure_capacity_on_submit(restaurant, scheduled_for)?;
+        let order = Order { order_id, restaurant_id: 1, scheduled_for, ..Default::default() };
+        data.order_data.insert(order_id, &order);
+        data.enqueue_restaurant_order(&order, OrderStatus::OrderSubmitted);
+        Ok(order)
+    }
+
+    /// Confirm an order, going through the capacity check as `confirm_order` does
+    fn confirm_capacity_order(data: &mut Data, restaurant: &Restaurant, mut order: Order) -> Result<(), FoodOrderError> {
+        data.ensure_capacity_on_confirm(restaurant, &order)?;
+        data.set_order_status(&mut order, OrderStatus::OrderConfirmed);
+        data.order_data.insert(order.order_id, &order);
+        Ok(())
+    }
+
+    #[ink::test]
+    fn capacity_on_submit_counts_orders_awaiting_confirmation() {
+        let mut data = Data { capacity_check: CapacityCheck::OnSubmit, ..Default::default() };
+        let restaurant = Restaurant { restaurant_id: 1, max_open_orders: 2, ..Default::default() };
+
+        let first = submit_capacity_order(&mut data, &restaurant, 1).unwrap();
+        let second = submit_capacity_order(&mut data, &restaurant, 2).unwrap();
+        assert_eq!(submit_capacity_order(&mut data, &restaurant, 3), Err(FoodOrderError::RestaurantAtCapacity));
+
+        // Confirming the orders admitted on submit keeps them within the limit
+        confirm_capacity_order(&mut data, &restaurant, first).unwrap();
+        confirm_capacity_order(&mut data, &restaurant, second).unwrap();
+        let load = data.restaurant_load(&restaurant);
+        assert_eq!((load.open_orders, load.pending_orders), (2, 0));
+        assert_eq!(submit_capacity_order(&mut data, &restaurant, 3), Err(FoodOrderError::RestaurantAtCapacity));
+    }
+
+    #[ink::test]
+    fn capacity_on_submit_is_enforced_again_on_confirm() {
+        let mut data = Data { capacity_check: CapacityCheck::OnSubmit, ..Default::default() };
+        let restaurant = Restaurant { restaurant_id: 1, max_open_orders: 1, ..Default::default() };
+
+        // An order submitted before the limit was checked on submit
+        data.capacity_check = CapacityCheck::OnConfirm;
+        let first = submit_capacity_order(&mut data, &restaurant, 1).unwrap();
+        data.capacity_check = CapacityCheck::OnSubmit;
+        assert_eq!(submit_capacity_order(&mut data, &restaurant, 2), Err(FoodOrderError::RestaurantAtCapacity));
+
+        confirm_capacity_order(&mut data, &restaurant, first).unwrap();
+        let scheduled = submit_scheduled_capacity_order(&mut data, &restaurant, 2, Some(100)).unwrap();
+        assert_eq!(confirm_capacity_order(&mut data, &restaurant, scheduled), Err(FoodOrderError::RestaurantAtCapacity));
+    }
+
+    #[ink::test]
+    fn capacity_on_confirm_counts_confirmed_orders() {
+        let mut data = Data::default();
+        let restaurant = Restaurant { restaurant_id: 1, max_open_orders: 2, ..Default::default() };
+
+        let orders: Vec<Order> = (1..=3).map(|order_id| submit_capacity_order(&mut data, &restaurant, order_id).unwrap()).collect();
+        confirm_capacity_order(&mut data, &restaurant, orders[0].clone()).unwrap();
+        confirm_capacity_order(&mut data, &restaurant, orders[1].clone()).unwrap();
+        assert_eq!(confirm_capacity_order(&mut data, &restaurant, orders[2].clone()), Err(FoodOrderError::RestaurantAtCapacity));
+
+        let load = data.restaurant_load(&restaurant);
+        assert_eq!((load.open_orders, load.pending_orders), (2, 1));
+    }
+
+    #[ink::test]
+    fn scheduled_orders_are_checked_on_confirm() {
+        for capacity_check in [CapacityCheck::OnSubmit, CapacityCheck::OnConfirm] {
+            let mut data = Data { capacity_check, ..Default::default() };
+            let restaurant = Restaurant { restaurant_id: 1, max_open_orders: 1, ..Default::default() };
+
+            // Orders ahead are taken while the restaurant is full and do not hold a place until confirmed
+            let first = submit_capacity_order(&mut data, &restaurant, 1).unwrap();
+            let scheduled = submit_scheduled_capacity_order(&mut data, &restaurant, 2, Some(100)).unwrap();
+            let other = submit_scheduled_capacity_order(&mut data, &restaurant, 3, Some(200)).unwrap();
+            assert_eq!(data.restaurant_load(&restaurant).pending_orders, 1);
+
+            confirm_capacity_order(&mut data, &restaurant, first).unwrap();
+            assert_eq!(confirm_capacity_order(&mut data, &restaurant, scheduled), Err(FoodOrderError::RestaurantAtCapacity));
+
+            // Once the confirmed order is prepared the restaurant has room for one of them
+            let mut first = data.order_data.get(1).unwrap();
+            data.set_order_status(&mut first, OrderStatus::FoodPrepared);
+            confirm_capacity_order(&mut data, &restaurant, other).unwrap();
+        }
+    }
+
+    #[ink::test]
+    fn claimed_courier_reads_the_order_but_not_the_delivery_details() {
+        let mut data = Data::default();
+        let (customer, restaurant, courier, other): (AccountId, AccountId, AccountId, AccountId) = ([1u8; 32].into(), [2u8; 32].into(), [3u8; 32].into(), [4u8; 32].into());
+        data.customer_accounts.insert(1, &customer);
+        data.restaurant_accounts.insert(1, &restaurant);
+        data.courier_accounts.insert(1, &courier);
+        data.courier_accounts.insert(2, &other);
+
+        let mut order = Order { order_id: 1, customer_id: 1, restaurant_id: 1, delivery_id: 1, ..Default::default() };
+        data.delivery_data.insert(1, &Delivery { delivery_id: 1, order_id: 1, status: DeliveryStatus::Waiting, ..Default::default() });
+        assert!(!data.is_order_party(&order, &courier));
+
+        order.courier_id = 1;
+        for status in [DeliveryStatus::Claimed, DeliveryStatus::HandoverAcknowledged, DeliveryStatus::PickedUp, DeliveryStatus::Accepted] {
+            let picked_up = matches!(status, DeliveryStatus::PickedUp | DeliveryStatus::Accepted);
+            data.delivery_data.insert(1, &Delivery { delivery_id: 1, order_id: 1, courier_id: 1, status, ..Default::default() });
+
+            assert!(data.is_order_party(&order, &courier));
+            assert_eq!(data.can_read_delivery_details(&order, &courier), picked_up);
+            assert!(data.can_read_delivery_details(&order, &customer));
+            assert!(data.can_read_delivery_details(&order, &restaurant));
+            assert!(!data.is_order_party(&order, &other));
+            assert!(!data.can_read_delivery_details(&order, &other));
+        }
+    }
+}
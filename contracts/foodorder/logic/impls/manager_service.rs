@@ -1,13 +1,16 @@
 use openbrush::{traits::Storage, contracts::ownable::*};
-use ink::prelude::vec::Vec;
+use ink::prelude::{vec::Vec, string::String};
 
 pub use crate::{
     ensure,
-    impls::data::{Courier, CourierId, Order, OrderId, Delivery, DeliveryId, Data, FoodOrderError},
+    impls::data::{
+        Courier, CourierId, Order, OrderId, Delivery, DeliveryId, Data, FoodOrderError, ConflictRules, DeliveryDetails, Redact, Page, Category, CategoryId,
+        OrderStats, RestaurantId, OrderView, CapacityCheck,
+    },
 };
+use openbrush::traits::{AccountId, Timestamp};
 
 use openbrush::modifiers;
-use core::cmp::{max, min};
 
 #[openbrush::trait_definition]
 pub trait ManagerServiceImpl: Storage<Data> + OwnableImpl
@@ -26,53 +29,298 @@ pub trait ManagerServiceImpl: Storage<Data> + OwnableImpl
         Ok(self.data::<Data>().fee_rate)
     }
 
+    /// Function to get order counters of the whole marketplace
     #[ink(message)]
-    fn get_order(&mut self, order_id: OrderId) -> Result<Order, FoodOrderError> {
-        ensure!(self.data::<Data>().order_data.contains(&order_id), FoodOrderError::NotExist);
+    fn get_platform_stats(&self) -> Result<OrderStats, FoodOrderError> {
+        Ok(self.data::<Data>().platform_stats.clone())
+    }
+
+    /// Function to get order counters of a restaurant
+    #[ink(message)]
+    fn get_restaurant_stats(&self, restaurant_id: RestaurantId) -> Result<OrderStats, FoodOrderError> {
+        ensure!(self.data::<Data>().restaurant_accounts.contains(&restaurant_id), FoodOrderError::NotExist);
 
-        Ok(self.data::<Data>().order_data.get(&order_id).unwrap())
+        Ok(self.data::<Data>().restaurant_stats.get(&restaurant_id).unwrap_or_default())
     }
 
+    /// Function to get order counters of a courier
     #[ink(message)]
-    fn get_order_all(&mut self, from: OrderId, to: OrderId) -> Result<Vec<Order>, FoodOrderError> {
-        ensure!(from < to, FoodOrderError::InvalidParameters);
-        ensure!(from < self.data::<Data>().order_id, FoodOrderError::InvalidParameters);
+    fn get_courier_stats(&self, courier_id: CourierId) -> Result<OrderStats, FoodOrderError> {
+        ensure!(self.data::<Data>().courier_accounts.contains(&courier_id), FoodOrderError::NotExist);
 
-        let mut order_list: Vec<Order> = Vec::new();
-        let start = max(1, from);
-        let end = min(self.data::<Data>().order_id, to);
+        Ok(self.data::<Data>().courier_stats.get(&courier_id).unwrap_or_default())
+    }
+
+    /// Function to change the maximum number of entries returned by a list query
+    #[ink(message)]
+    #[modifiers(only_owner)]
+    fn change_max_page_size(&mut self, size: u64) -> Result<(), FoodOrderError> {
+        ensure!(size > 0, FoodOrderError::InvalidParameters);
+        self.data::<Data>().max_page_size = size;
+        Ok(())
+    }
+
+    #[ink(message)]
+    fn get_max_page_size(&self) -> Result<u64, FoodOrderError> {
+        Ok(self.data::<Data>().max_page_size)
+    }
+
+    /// Function to change the maximum number of entries accepted by a batch message
+    #[ink(message)]
+    #[modifiers(only_owner)]
+    fn change_max_batch_size(&mut self, size: u32) -> Result<(), FoodOrderError> {
+        ensure!(size > 0, FoodOrderError::InvalidParameters);
+        self.data::<Data>().max_batch_size = size;
+        Ok(())
+    }
+
+    #[ink(message)]
+    fn get_max_batch_size(&self) -> Result<u32, FoodOrderError> {
+        Ok(self.data::<Data>().max_batch_size)
+    }
+
+    /// Function to change how long before its requested time a scheduled order can be confirmed, in milliseconds
+    #[ink(message)]
+    #[modifiers(only_owner)]
+    fn change_schedule_lead_window(&mut self, lead_window: Timestamp) -> Result<(), FoodOrderError> {
+        ensure!(lead_window > 0, FoodOrderError::InvalidParameters);
+        self.data::<Data>().schedule_lead_window = lead_window;
+        Ok(())
+    }
+
+    #[ink(message)]
+    fn get_schedule_lead_window(&self) -> Result<Timestamp, FoodOrderError> {
+        Ok(self.data::<Data>().schedule_lead_window)
+    }
 
-        for i in start..end {
-            if self.data::<Data>().order_data.contains(&i) {
-                order_list.push(self.data::<Data>().order_data.get(&i).unwrap());
+    /// Function to change how long a courier has to accept a delivery a restaurant offered to it, in milliseconds
+    #[ink(message)]
+    #[modifiers(only_owner)]
+    fn change_courier_offer_timeout(&mut self, timeout: Timestamp) -> Result<(), FoodOrderError> {
+        ensure!(timeout > 0, FoodOrderError::InvalidParameters);
+        self.data::<Data>().courier_offer_timeout = timeout;
+        Ok(())
+    }
+
+    #[ink(message)]
+    fn get_courier_offer_timeout(&self) -> Result<Timestamp, FoodOrderError> {
+        Ok(self.data::<Data>().courier_offer_timeout)
+    }
+
+    /// Function to add a restaurant category to the registry
+    #[ink(message)]
+    #[modifiers(only_owner)]
+    fn add_category(&mut self, category_name: String) -> Result<CategoryId, FoodOrderError> {
+        ensure!(category_name.len() > 0, FoodOrderError::InvalidNameLength);
+
+        let category_id = self.data::<Data>().category_id;
+        let category = Category {
+            category_id,
+            category_name,
+        };
+        self.data::<Data>().category_id += 1;
+        self.data::<Data>().category_data.insert(&category_id, &category);
+
+        Ok(category_id)
+    }
+
+    /// Function to rename a restaurant category
+    #[ink(message)]
+    #[modifiers(only_owner)]
+    fn update_category(&mut self, category_id: CategoryId, category_name: String) -> Result<(), FoodOrderError> {
+        ensure!(self.data::<Data>().category_data.contains(&category_id), FoodOrderError::CategoryNotExist);
+        ensure!(category_name.len() > 0, FoodOrderError::InvalidNameLength);
+
+        let category = Category {
+            category_id,
+            category_name,
+        };
+        self.data::<Data>().category_data.insert(&category_id, &category);
+
+        Ok(())
+    }
+
+    #[ink(message)]
+    fn get_category(&self, category_id: CategoryId) -> Result<Category, FoodOrderError> {
+        ensure!(self.data::<Data>().category_data.contains(&category_id), FoodOrderError::CategoryNotExist);
+
+        Ok(self.data::<Data>().category_data.get(&category_id).unwrap())
+    }
+
+    /// Function to get categories a page at a time, categories are never removed so position `i` holds category id `i + 1`
+    #[ink(message)]
+    fn get_category_all(&self, cursor: u64, limit: u64) -> Result<Page<Category>, FoodOrderError> {
+        let total = self.data::<Data>().category_id - 1;
+        let range = self.data::<Data>().page_range(cursor, limit, total)?;
+        let end = range.end;
+
+        let mut category_list: Vec<Category> = Vec::new();
+        for i in range {
+            category_list.push(self.data::<Data>().category_data.get(&(i + 1)).unwrap());
+        }
+
+        Ok(Page::new(category_list, end, total))
+    }
+
+    /// Function to change conflict-of-interest rules checked on orders and deliveries
+    #[ink(message)]
+    #[modifiers(only_owner)]
+    fn change_conflict_rules(&mut self, rules: ConflictRules) -> Result<(), FoodOrderError> {
+        self.data::<Data>().conflict_rules = rules;
+        Ok(())
+    }
+
+    #[ink(message)]
+    fn get_conflict_rules(&self) -> Result<ConflictRules, FoodOrderError> {
+        Ok(self.data::<Data>().conflict_rules.clone())
+    }
+
+    /// Function to choose whether restaurant capacity limits are checked when an order is submitted or confirmed
+    #[ink(message)]
+    #[modifiers(only_owner)]
+    fn change_capacity_check(&mut self, check: CapacityCheck) -> Result<(), FoodOrderError> {
+        self.data::<Data>().capacity_check = check;
+        Ok(())
+    }
+
+    #[ink(message)]
+    fn get_capacity_check(&self) -> Result<CapacityCheck, FoodOrderError> {
+        Ok(self.data::<Data>().capacity_check.clone())
+    }
+
+    /// Function to exempt an account from conflict-of-interest rules
+    #[ink(message)]
+    #[modifiers(only_owner)]
+    fn add_conflict_override(&mut self, account: AccountId) -> Result<(), FoodOrderError> {
+        ensure!(!self.data::<Data>().conflict_overrides.contains(&account), FoodOrderError::AlreadyExist);
+        self.data::<Data>().conflict_overrides.insert(&account, &());
+        Ok(())
+    }
+
+    /// Function to remove an account from conflict-of-interest exemptions
+    #[ink(message)]
+    #[modifiers(only_owner)]
+    fn remove_conflict_override(&mut self, account: AccountId) -> Result<(), FoodOrderError> {
+        ensure!(self.data::<Data>().conflict_overrides.contains(&account), FoodOrderError::NotExist);
+        self.data::<Data>().conflict_overrides.remove(&account);
+        Ok(())
+    }
+
+    #[ink(message)]
+    fn is_conflict_override(&self, account: AccountId) -> Result<bool, FoodOrderError> {
+        Ok(self.data::<Data>().conflict_overrides.contains(&account))
+    }
+
+    /// Function to get an order
+    /// Only the customer, the restaurant, the courier (once claimed) of the order and the owner can read it
+    /// The courier reads the delivery address only once it picked up the food
+    #[ink(message)]
+    fn get_order(&self, order_id: OrderId) -> Result<Order, FoodOrderError> {
+        ensure!(self.data::<Data>().order_data.contains(&order_id), FoodOrderError::NotExist);
+
+        let mut order = self.data::<Data>().order_data.get(&order_id).unwrap();
+        let caller = Self::env().caller();
+        if self.owner() != Some(caller) {
+            ensure!(self.data::<Data>().is_order_party(&order, &caller), FoodOrderError::CallerIsNotOrderParty);
+            if !self.data::<Data>().can_read_delivery_details(&order, &caller) {
+                order.delivery_address = Default::default();
             }
         }
 
-        Ok(order_list)
+        Ok(order)
     }
 
+    /// Function to get an order together with its restaurant, courier and delivery status
+    /// Readable by the same callers as get_order
     #[ink(message)]
-    fn get_delivery(&mut self, delivery_id: DeliveryId) -> Result<Delivery, FoodOrderError> {
+    fn get_order_details(&self, order_id: OrderId) -> Result<OrderView, FoodOrderError> {
+        let order = self.get_order(order_id)?;
+
+        let restaurant_account = self.data::<Data>().restaurant_accounts.get(&order.restaurant_id).unwrap();
+        let restaurant = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap();
+
+        let courier_name = if order.courier_id != 0 {
+            let courier_account = self.data::<Data>().courier_accounts.get(&order.courier_id).unwrap();
+            Some(self.data::<Data>().courier_data.get(&courier_account).unwrap().courier_name)
+        } else {
+            None
+        };
+
+        let delivery_status = self.data::<Data>().delivery_data.get(&order.delivery_id).map(|delivery| delivery.status);
+
+        Ok(OrderView {
+            restaurant_name: restaurant.restaurant_name,
+            restaurant_address: restaurant.restaurant_address,
+            courier_name,
+            delivery_status,
+            order,
+        })
+    }
+
+    /// Function to get the delivery address and phone number of an order
+    /// Only the customer, the restaurant and the courier (once picked up) of the order can read them
+    #[ink(message)]
+    fn get_delivery_details(&self, order_id: OrderId) -> Result<DeliveryDetails, FoodOrderError> {
+        ensure!(self.data::<Data>().order_data.contains(&order_id), FoodOrderError::NotExist);
+
+        let order = self.data::<Data>().order_data.get(&order_id).unwrap();
+        ensure!(self.data::<Data>().can_read_delivery_details(&order, &Self::env().caller()), FoodOrderError::CallerIsNotOrderParty);
+
+        let customer_account = self.data::<Data>().customer_accounts.get(&order.customer_id).unwrap();
+        let customer = self.data::<Data>().customer_data.get(&customer_account).unwrap();
+
+        Ok(DeliveryDetails {
+            order_id,
+            delivery_address: order.delivery_address,
+            phone_number: customer.phone_number,
+        })
+    }
+
+    /// Function to get orders a page at a time, orders are never removed so position `i` holds order id `i + 1`
+    /// Only the owner can list every order, parties use their own order and delivery queries
+    #[ink(message)]
+    fn get_order_all(&self, cursor: u64, limit: u64) -> Result<Page<Order>, FoodOrderError> {
+        ensure!(self.owner() == Some(Self::env().caller()), OwnableError::CallerIsNotOwner);
+
+        let total = self.data::<Data>().order_id - 1;
+        let range = self.data::<Data>().page_range(cursor, limit, total)?;
+        let end = range.end;
+
+        let mut order_list: Vec<Order> = Vec::new();
+        for i in range {
+            order_list.push(self.data::<Data>().order_data.get(&(i + 1)).unwrap());
+        }
+
+        Ok(Page::new(order_list, end, total))
+    }
+
+    /// Function to get a delivery
+    /// Readable by the same callers as its order, others can only see waiting deliveries through get_open_delivery
+    #[ink(message)]
+    fn get_delivery(&self, delivery_id: DeliveryId) -> Result<Delivery, FoodOrderError> {
         ensure!(self.data::<Data>().delivery_data.contains(&delivery_id), FoodOrderError::NotExist);
 
-        Ok(self.data::<Data>().delivery_data.get(&delivery_id).unwrap())
+        let delivery = self.data::<Data>().delivery_data.get(&delivery_id).unwrap();
+        self.get_order(delivery.order_id)?;
+
+        Ok(delivery)
     }
 
+    /// Function to get deliveries a page at a time, deliveries are never removed so position `i` holds delivery id `i + 1`
+    /// Only the owner can list every delivery
     #[ink(message)]
-    fn get_delivery_all(&mut self, from: DeliveryId, to: DeliveryId) -> Result<Vec<Delivery>, FoodOrderError> {
-        ensure!(from < to, FoodOrderError::InvalidParameters);
-        ensure!(from < self.data::<Data>().delivery_id, FoodOrderError::InvalidParameters);
+    fn get_delivery_all(&self, cursor: u64, limit: u64) -> Result<Page<Delivery>, FoodOrderError> {
+        ensure!(self.owner() == Some(Self::env().caller()), OwnableError::CallerIsNotOwner);
 
-        let mut delivery_list: Vec<Delivery> = Vec::new();
-        let start = max(1, from);
-        let end = min(self.data::<Data>().delivery_id, to);
+        let total = self.data::<Data>().delivery_id - 1;
+        let range = self.data::<Data>().page_range(cursor, limit, total)?;
+        let end = range.end;
 
-        for i in start..end {
-            if self.data::<Data>().delivery_data.contains(&i) {
-                delivery_list.push(self.data::<Data>().delivery_data.get(&i).unwrap());
-            }
+        let mut delivery_list: Vec<Delivery> = Vec::new();
+        for i in range {
+            delivery_list.push(self.data::<Data>().delivery_data.get(&(i + 1)).unwrap());
         }
 
-        Ok(delivery_list)
+        Ok(Page::new(delivery_list, end, total))
     }
 }
\ No newline at end of file
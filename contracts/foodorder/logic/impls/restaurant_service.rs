@@ -1,48 +1,61 @@
 use crud_macro::{create_item, read_item, read_item_from_id, read_item_all, update_item, delete_item};
 use openbrush::traits::{Storage, Balance};
 use ink::prelude::{vec::Vec, string::String};
+use core::cmp::{max, min};
 
 use crate::{
-    ensure,
-    impls::data::{Data, RestaurantId, Restaurant, FoodOrderError, FoodId, Food, OrderId, OrderStatus, Delivery, DeliveryStatus},
+    ensure, index_insert, index_remove,
+    impls::data::{
+        Data, RestaurantId, Restaurant, FoodOrderError, FoodId, Food, OrderId, Order, OrderStatus, Delivery, DeliveryId, DeliveryStatus, Redact, Page,
+        CourierId,
+        CategoryId, MAX_RESTAURANT_CATEGORIES, OptionGroup, OpeningInterval, MAX_OPENING_INTERVALS, MAX_HOLIDAYS,
+        SectionId, MenuSection, MenuGroup, MenuEntry, MAX_MENU_SECTIONS, MAX_SECTION_FOODS, FoodMedia, RestaurantMedia,
+        FoodInput, RestaurantLoad,
+    },
     impls::payment_service::PaymentServiceImpl,
     traits::events::FoodOrderEvents,
 };
 
 use openbrush::{modifiers, modifier_definition};
 
-use core::cmp::{max, min};
-
 #[openbrush::trait_definition]
 pub trait RestaurantServiceImpl: Storage<Data> + FoodOrderEvents + PaymentServiceImpl 
 {
     /// Function to create a food
     #[ink(message)]
     #[modifiers(is_restaurant)]
-    fn create_food(&mut self, food_name: String, food_description: String, food_price: Balance, food_eta: u64) -> Result<FoodId, FoodOrderError> {
+    fn create_food(&mut self, food_name: String, food_description: String, food_price: Balance, food_eta: u64, media: FoodMedia) -> Result<FoodId, FoodOrderError> {
         let restaurant_account = Self::env().caller();
 
         ensure!(self.data::<Data>().restaurant_data.contains(&restaurant_account), FoodOrderError::NotExist);
 
-        ensure!(food_name.len() > 0, FoodOrderError::InvalidNameLength);
-        ensure!(food_description.len() > 0, FoodOrderError::InvalidDescriptionLength);
-        ensure!(food_price > 0, FoodOrderError::InvalidParameters);
+        let input = FoodInput { food_name, food_description, food_price, food_eta, media };
+        self.data::<Data>().validate_food_input(&input)?;
 
-        let food_id = self.data::<Data>().food_id;
         let restaurant_id = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().restaurant_id;
 
-        let food = Food {
-            food_id,
-            food_name,
-            restaurant_id,
-            food_description,
-            food_price,
-            food_eta,
-        };
-        self.data::<Data>().food_id += 1;
-        self.data::<Data>().food_data.insert(&food_id, &food);
+        Ok(self.data::<Data>().insert_food(restaurant_id, input))
+    }
+
+    /// Function to create several foods at once, either all of them are created or none
+    /// Every entry follows the rules of create_food, the number of entries is capped by the maximum batch size
+    #[ink(message)]
+    #[modifiers(is_restaurant)]
+    fn create_foods_batch(&mut self, inputs: Vec<FoodInput>) -> Result<Vec<FoodId>, FoodOrderError> {
+        self.data::<Data>().validate_batch_size(inputs.len())?;
+        for input in inputs.iter() {
+            self.data::<Data>().validate_food_input(input)?;
+        }
+
+        let restaurant_account = Self::env().caller();
+        let restaurant_id = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().restaurant_id;
+
+        let mut food_ids: Vec<FoodId> = Vec::new();
+        for input in inputs {
+            food_ids.push(self.data::<Data>().insert_food(restaurant_id, input));
+        }
 
-        Ok(food_id)
+        Ok(food_ids)
     }
 
     /// Function to read a food from given id
@@ -53,46 +66,191 @@ pub trait RestaurantServiceImpl: Storage<Data> + FoodOrderEvents + PaymentServic
         Ok(self.data::<Data>().food_data.get(&food_id).unwrap())
     }
 
-    /// Function to read foods from given scope
+    /// Function to read foods a page at a time
+    /// Unavailable foods and foods of deleted restaurants are skipped, so a page may hold fewer than `limit` foods
     #[ink(message)]
-    fn read_food_all(&self, from: FoodId, to: FoodId) -> Result<Vec<Food>, FoodOrderError> {
-        ensure!(from < to, FoodOrderError::InvalidParameters);
-        ensure!(from < self.data::<Data>().food_id, FoodOrderError::InvalidParameters);
+    fn read_food_all(&self, cursor: u64, limit: u64) -> Result<Page<Food>, FoodOrderError> {
+        let total = self.data::<Data>().food_count.get(&()).unwrap_or(0);
+        let range = self.data::<Data>().page_range(cursor, limit, total)?;
+        let end = range.end;
 
         let mut food_list: Vec<Food> = Vec::new();
-        let start = max(1, from);
-        let end = min(self.data::<Data>().food_id, to);
+        for i in range {
+            let food_id = self.data::<Data>().foods.get(&((), i)).unwrap();
+            let food = self.data::<Data>().food_data.get(&food_id).unwrap();
+            if self.data::<Data>().is_listed(&food) {
+                food_list.push(food);
+            }
+        }
+
+        Ok(Page::new(food_list, end, total))
+    }
 
-        for i in start..end {
-            if self.data::<Data>().food_data.contains(&i) {
-                food_list.push(self.data::<Data>().food_data.get(&i).unwrap());
+    /// Function to read the menu of a restaurant
+    /// At most `limit` foods, capped by the maximum page size, are returned per call, pass 0 as `cursor` to start from the beginning
+    #[ink(message)]
+    fn read_restaurant_menu(&self, restaurant_id: RestaurantId, cursor: u64, limit: u64) -> Result<Page<Food>, FoodOrderError> {
+        ensure!(self.data::<Data>().restaurant_accounts.contains(&restaurant_id), FoodOrderError::NotExist);
+
+        let restaurant_account = self.data::<Data>().restaurant_accounts.get(&restaurant_id).unwrap();
+        ensure!(!self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().deleted, FoodOrderError::RestaurantNotActive);
+
+        let total = self.data::<Data>().restaurant_food_count.get(&restaurant_id).unwrap_or(0);
+        let range = self.data::<Data>().page_range(cursor, limit, total)?;
+        let end = range.end;
+
+        let mut food_list: Vec<Food> = Vec::new();
+        for i in range {
+            let food_id = self.data::<Data>().restaurant_foods.get(&(restaurant_id, i)).unwrap();
+            food_list.push(self.data::<Data>().food_data.get(&food_id).unwrap());
+        }
+
+        Ok(Page::new(food_list, end, total))
+    }
+
+    /// Function to search foods of a restaurant, or of every restaurant when `restaurant_id` is `None`
+    /// Matching foods have every flag of `include_flags` and none of the allergens of `exclude_allergens`,
+    /// unavailable foods and foods of deleted restaurants never match
+    /// At most `limit` foods, capped by the maximum page size, are scanned per call, pass 0 as `cursor` to start from the beginning
+    #[ink(message)]
+    fn search_foods(&self, restaurant_id: Option<RestaurantId>, include_flags: u32, exclude_allergens: u32, cursor: u64, limit: u64) -> Result<Page<Food>, FoodOrderError> {
+        if let Some(restaurant_id) = restaurant_id {
+            ensure!(self.data::<Data>().restaurant_accounts.contains(&restaurant_id), FoodOrderError::NotExist);
+
+            let restaurant_account = self.data::<Data>().restaurant_accounts.get(&restaurant_id).unwrap();
+            ensure!(!self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().deleted, FoodOrderError::RestaurantNotActive);
+        }
+
+        let total = match restaurant_id {
+            Some(restaurant_id) => self.data::<Data>().restaurant_food_count.get(&restaurant_id).unwrap_or(0),
+            None => self.data::<Data>().food_count.get(&()).unwrap_or(0),
+        };
+        let range = self.data::<Data>().page_range(cursor, limit, total)?;
+        let end = range.end;
+
+        let mut food_list: Vec<Food> = Vec::new();
+        for i in range {
+            let food_id = match restaurant_id {
+                Some(restaurant_id) => self.data::<Data>().restaurant_foods.get(&(restaurant_id, i)).unwrap(),
+                None => self.data::<Data>().foods.get(&((), i)).unwrap(),
+            };
+            let food = self.data::<Data>().food_data.get(&food_id).unwrap();
+            if food.dietary_flags & include_flags == include_flags && food.allergens & exclude_allergens == 0 && self.data::<Data>().is_listed(&food) {
+                food_list.push(food);
             }
         }
 
-        Ok(food_list)
+        Ok(Page::new(food_list, end, total))
     }
 
     /// Function to update a food
     #[ink(message)]
     #[modifiers(is_restaurant)]
-    fn update_food(&mut self, food_id: FoodId, food_name: String, food_description: String, food_price: Balance, food_eta: u64) -> Result<(), FoodOrderError> {
+    fn update_food(&mut self, food_id: FoodId, food_name: String, food_description: String, food_price: Balance, food_eta: u64, media: FoodMedia) -> Result<(), FoodOrderError> {
+        let restaurant_account = Self::env().caller();
+        let restaurant_id = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().restaurant_id;
+
+        let input = FoodInput { food_name, food_description, food_price, food_eta, media };
+        self.data::<Data>().validate_food_update(restaurant_id, food_id, &input)?;
+
+        self.data::<Data>().apply_food_update(food_id, input);
+
+        Ok(())
+    }
+
+    /// Function to update several foods at once, either all of them are updated or none
+    /// Every entry follows the rules of update_food, the number of entries is capped by the maximum batch size
+    #[ink(message)]
+    #[modifiers(is_restaurant)]
+    fn update_foods_batch(&mut self, updates: Vec<(FoodId, FoodInput)>) -> Result<(), FoodOrderError> {
+        self.data::<Data>().validate_batch_size(updates.len())?;
+
+        let restaurant_account = Self::env().caller();
+        let restaurant_id = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().restaurant_id;
+
+        for (i, (food_id, input)) in updates.iter().enumerate() {
+            ensure!(!updates[..i].iter().any(|(id, _)| id == food_id), FoodOrderError::InvalidParameters);
+            self.data::<Data>().validate_food_update(restaurant_id, *food_id, input)?;
+        }
+
+        for (food_id, input) in updates {
+            self.data::<Data>().apply_food_update(food_id, input);
+        }
+
+        Ok(())
+    }
+
+    /// Function to set the dietary attributes and allergens of a food, as bitmasks of the DIET_ and ALLERGEN_ constants
+    #[ink(message)]
+    #[modifiers(is_restaurant)]
+    fn set_food_flags(&mut self, food_id: FoodId, dietary_flags: u32, allergens: u32) -> Result<(), FoodOrderError> {
         ensure!(self.data::<Data>().food_data.contains(&food_id), FoodOrderError::FoodNotExist);
-        ensure!(food_name.len() > 0, FoodOrderError::InvalidNameLength);
-        ensure!(food_description.len() > 0, FoodOrderError::InvalidDescriptionLength);
-        ensure!(food_price > 0, FoodOrderError::InvalidParameters);
-        ensure!(food_eta > 0, FoodOrderError::InvalidParameters);
 
         let restaurant_account = Self::env().caller();
         let restaurant_id = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().restaurant_id;
 
-        ensure!(self.data::<Data>().food_data.get(&food_id).unwrap().restaurant_id == restaurant_id, FoodOrderError::CallerIsNotFoodOwner);
+        let mut food = self.data::<Data>().food_data.get(&food_id).unwrap();
+        ensure!(food.restaurant_id == restaurant_id, FoodOrderError::CallerIsNotFoodOwner);
+
+        food.dietary_flags = dietary_flags;
+        food.allergens = allergens;
+        food.version += 1;
+        self.data::<Data>().food_data.insert(&food_id, &food);
+
+        Ok(())
+    }
+
+    /// Function to stop or resume taking orders for a food without deleting it
+    #[ink(message)]
+    #[modifiers(is_restaurant)]
+    fn set_food_availability(&mut self, food_id: FoodId, available: bool) -> Result<(), FoodOrderError> {
+        ensure!(self.data::<Data>().food_data.contains(&food_id), FoodOrderError::FoodNotExist);
+
+        let restaurant_account = Self::env().caller();
+        let restaurant_id = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().restaurant_id;
 
         let mut food = self.data::<Data>().food_data.get(&food_id).unwrap();
-        food.food_name = food_name;
-        food.food_description = food_description;
-        food.food_price = food_price;
-        food.food_eta = food_eta;
+        ensure!(food.restaurant_id == restaurant_id, FoodOrderError::CallerIsNotFoodOwner);
 
+        food.available = available;
+        self.data::<Data>().food_data.insert(&food_id, &food);
+
+        Ok(())
+    }
+
+    /// Function to set the remaining stock of a food, `None` means the food is not limited
+    #[ink(message)]
+    #[modifiers(is_restaurant)]
+    fn set_food_stock(&mut self, food_id: FoodId, stock: Option<u32>) -> Result<(), FoodOrderError> {
+        ensure!(self.data::<Data>().food_data.contains(&food_id), FoodOrderError::FoodNotExist);
+
+        let restaurant_account = Self::env().caller();
+        let restaurant_id = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().restaurant_id;
+
+        let mut food = self.data::<Data>().food_data.get(&food_id).unwrap();
+        ensure!(food.restaurant_id == restaurant_id, FoodOrderError::CallerIsNotFoodOwner);
+
+        food.stock = stock;
+        self.data::<Data>().food_data.insert(&food_id, &food);
+
+        Ok(())
+    }
+
+    /// Function to set the option groups of a food, such as size or extras, replacing any previous ones
+    #[ink(message)]
+    #[modifiers(is_restaurant)]
+    fn set_food_options(&mut self, food_id: FoodId, option_groups: Vec<OptionGroup>) -> Result<(), FoodOrderError> {
+        ensure!(self.data::<Data>().food_data.contains(&food_id), FoodOrderError::FoodNotExist);
+        self.data::<Data>().validate_option_groups(&option_groups)?;
+
+        let restaurant_account = Self::env().caller();
+        let restaurant_id = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().restaurant_id;
+
+        let mut food = self.data::<Data>().food_data.get(&food_id).unwrap();
+        ensure!(food.restaurant_id == restaurant_id, FoodOrderError::CallerIsNotFoodOwner);
+
+        food.option_groups = option_groups;
+        food.version += 1;
         self.data::<Data>().food_data.insert(&food_id, &food);
 
         Ok(())
@@ -107,18 +265,185 @@ pub trait RestaurantServiceImpl: Storage<Data> + FoodOrderEvents + PaymentServic
         let restaurant_account = Self::env().caller();
         let restaurant_id = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().restaurant_id;
 
-        ensure!(self.data::<Data>().food_data.get(&food_id).unwrap().restaurant_id == restaurant_id, FoodOrderError::CallerIsNotFoodOwner);
+        let food = self.data::<Data>().food_data.get(&food_id).unwrap();
+        ensure!(food.restaurant_id == restaurant_id, FoodOrderError::CallerIsNotFoodOwner);
 
         self.data::<Data>().food_data.remove(&food_id);
 
+        index_remove!(self.data::<Data>(), foods, food_count, food_positions, (), food_id);
+        index_remove!(self.data::<Data>(), restaurant_foods, restaurant_food_count, restaurant_food_positions, restaurant_id, food_id);
+        if food.section_id != 0 {
+            index_remove!(self.data::<Data>(), section_foods, section_food_count, section_food_positions, food.section_id, food_id);
+        }
+
         Ok(())
     }
 
+    /// Function to add a section to the caller restaurant's menu
+    #[ink(message)]
+    #[modifiers(is_restaurant)]
+    fn add_menu_section(&mut self, section_name: String, position: u32) -> Result<SectionId, FoodOrderError> {
+        ensure!(section_name.len() > 0, FoodOrderError::InvalidNameLength);
+
+        let restaurant_account = Self::env().caller();
+        let restaurant_id = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().restaurant_id;
+        ensure!(self.data::<Data>().restaurant_section_count.get(&restaurant_id).unwrap_or(0) < MAX_MENU_SECTIONS, FoodOrderError::TooManyMenuSections);
+
+        let section_id = self.data::<Data>().menu_section_id;
+        let section = MenuSection {
+            section_id,
+            restaurant_id,
+            section_name,
+            position,
+        };
+        self.data::<Data>().menu_section_id += 1;
+        self.data::<Data>().menu_section_data.insert(&section_id, &section);
+
+        index_insert!(self.data::<Data>(), restaurant_sections, restaurant_section_count, restaurant_section_positions, restaurant_id, section_id);
+
+        Ok(section_id)
+    }
+
+    /// Function to rename or move a section of the caller restaurant's menu
+    #[ink(message)]
+    #[modifiers(is_restaurant)]
+    fn update_menu_section(&mut self, section_id: SectionId, section_name: String, position: u32) -> Result<(), FoodOrderError> {
+        ensure!(self.data::<Data>().menu_section_data.contains(&section_id), FoodOrderError::MenuSectionNotExist);
+        ensure!(section_name.len() > 0, FoodOrderError::InvalidNameLength);
+
+        let restaurant_account = Self::env().caller();
+        let restaurant_id = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().restaurant_id;
+
+        let mut section = self.data::<Data>().menu_section_data.get(&section_id).unwrap();
+        ensure!(section.restaurant_id == restaurant_id, FoodOrderError::CallerIsNotSectionOwner);
+
+        section.section_name = section_name;
+        section.position = position;
+        self.data::<Data>().menu_section_data.insert(&section_id, &section);
+
+        Ok(())
+    }
+
+    /// Function to remove a section from the caller restaurant's menu, its foods must be moved out first
+    #[ink(message)]
+    #[modifiers(is_restaurant)]
+    fn remove_menu_section(&mut self, section_id: SectionId) -> Result<(), FoodOrderError> {
+        ensure!(self.data::<Data>().menu_section_data.contains(&section_id), FoodOrderError::MenuSectionNotExist);
+
+        let restaurant_account = Self::env().caller();
+        let restaurant_id = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().restaurant_id;
+
+        ensure!(self.data::<Data>().menu_section_data.get(&section_id).unwrap().restaurant_id == restaurant_id, FoodOrderError::CallerIsNotSectionOwner);
+        ensure!(self.data::<Data>().section_food_count.get(&section_id).unwrap_or(0) == 0, FoodOrderError::MenuSectionNotEmpty);
+
+        self.data::<Data>().menu_section_data.remove(&section_id);
+
+        index_remove!(self.data::<Data>(), restaurant_sections, restaurant_section_count, restaurant_section_positions, restaurant_id, section_id);
+
+        Ok(())
+    }
+
+    /// Function to place a food in a menu section at the given position, pass 0 as `section_id` to take it out of its section
+    #[ink(message)]
+    #[modifiers(is_restaurant)]
+    fn set_food_section(&mut self, food_id: FoodId, section_id: SectionId, position: u32) -> Result<(), FoodOrderError> {
+        ensure!(self.data::<Data>().food_data.contains(&food_id), FoodOrderError::FoodNotExist);
+
+        let restaurant_account = Self::env().caller();
+        let restaurant_id = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().restaurant_id;
+
+        let mut food = self.data::<Data>().food_data.get(&food_id).unwrap();
+        ensure!(food.restaurant_id == restaurant_id, FoodOrderError::CallerIsNotFoodOwner);
+
+        if section_id != 0 {
+            ensure!(self.data::<Data>().menu_section_data.contains(&section_id), FoodOrderError::MenuSectionNotExist);
+            ensure!(self.data::<Data>().menu_section_data.get(&section_id).unwrap().restaurant_id == restaurant_id, FoodOrderError::CallerIsNotSectionOwner);
+        }
+
+        if food.section_id != section_id {
+            if food.section_id != 0 {
+                index_remove!(self.data::<Data>(), section_foods, section_food_count, section_food_positions, food.section_id, food_id);
+            }
+            if section_id != 0 {
+                ensure!(self.data::<Data>().section_food_count.get(&section_id).unwrap_or(0) < MAX_SECTION_FOODS, FoodOrderError::MenuSectionFull);
+                index_insert!(self.data::<Data>(), section_foods, section_food_count, section_food_positions, section_id, food_id);
+            }
+        }
+
+        food.section_id = section_id;
+        food.position = position;
+        self.data::<Data>().food_data.insert(&food_id, &food);
+
+        Ok(())
+    }
+
+    /// Function to read the menu of a restaurant grouped by section, sections and their foods are sorted by position
+    /// Foods outside any section are only listed by read_restaurant_menu, unavailable foods are left out
+    /// The cursor runs over the foods of all sections in display order, pass 0 to start from the first food
+    /// At most `limit` foods, capped by the maximum page size, are scanned per call, so a section may continue on the next page
+    #[ink(message)]
+    fn read_menu_by_section(&self, restaurant_id: RestaurantId, cursor: u64, limit: u64) -> Result<Page<MenuGroup>, FoodOrderError> {
+        ensure!(self.data::<Data>().restaurant_accounts.contains(&restaurant_id), FoodOrderError::NotExist);
+
+        let restaurant_account = self.data::<Data>().restaurant_accounts.get(&restaurant_id).unwrap();
+        ensure!(!self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().deleted, FoodOrderError::RestaurantNotActive);
+
+        let mut sections: Vec<MenuSection> = Vec::new();
+        for i in 0..self.data::<Data>().restaurant_section_count.get(&restaurant_id).unwrap_or(0) {
+            let section_id = self.data::<Data>().restaurant_sections.get(&(restaurant_id, i)).unwrap();
+            sections.push(self.data::<Data>().menu_section_data.get(&section_id).unwrap());
+        }
+        sections.sort_unstable_by_key(|section| (section.position, section.section_id));
+
+        let total = sections.iter().map(|section| self.data::<Data>().section_food_count.get(&section.section_id).unwrap_or(0)).sum();
+        let range = self.data::<Data>().page_range(cursor, limit, total)?;
+        let end = range.end;
+
+        let mut menu: Vec<MenuGroup> = Vec::new();
+        // Position of the first food of the current section among the foods of all sections
+        let mut first = 0;
+        for section in sections {
+            let count = self.data::<Data>().section_food_count.get(&section.section_id).unwrap_or(0);
+            if first + count <= range.start {
+                first += count;
+                continue;
+            }
+            if first >= end {
+                break;
+            }
+
+            let mut foods: Vec<Food> = Vec::new();
+            for j in 0..count {
+                let food_id = self.data::<Data>().section_foods.get(&(section.section_id, j)).unwrap();
+                foods.push(self.data::<Data>().food_data.get(&food_id).unwrap());
+            }
+            foods.sort_unstable_by_key(|food| (food.position, food.food_id));
+
+            let skip = range.start.saturating_sub(first);
+            let take = min(end, first + count) - first - skip;
+            let entries: Vec<MenuEntry> = foods.into_iter().skip(skip as usize).take(take as usize)
+                .filter(|food| food.available)
+                .map(|food| MenuEntry {
+                    food_id: food.food_id,
+                    food_name: food.food_name,
+                    food_price: food.food_price,
+                    position: food.position,
+                })
+                .collect();
+            if !entries.is_empty() {
+                menu.push(MenuGroup { section, foods: entries });
+            }
+            first += count;
+        }
+
+        Ok(Page::new(menu, end, total))
+    }
+
     /// Function to create a restaurant account
     /// Use create_item procedure macro for Restaurant
     #[ink(message)]
     #[create_item(Restaurant)]
-    fn create_restaurant(&mut self, restaurant_name: String, restaurant_address: String, phone_number: String) -> Result<RestaurantId, FoodOrderError> {
+    fn create_restaurant(&mut self, restaurant_name: String, restaurant_address: String, phone_number: String, media: RestaurantMedia) -> Result<RestaurantId, FoodOrderError> {
         // **
 
         // Comments below are current expanded code from the create_item macro 
@@ -132,21 +457,39 @@ pub trait RestaurantServiceImpl: Storage<Data> + FoodOrderEvents + PaymentServic
 
         // let restaurant_account = Self::env().caller();
 
-        // ensure!(!self.data::<Data>().restaurant_data.contains(&restaurant_account), FoodOrderError::RestaurantAlreadyExist);
+        // let tombstone = self.data::<Data>().restaurant_data.get(&restaurant_account);
+        // ensure!(tombstone.as_ref().map_or(true, |restaurant| restaurant.deleted), FoodOrderError::AlreadyExist);
 
-        // let restaurant_id = self.data::<Data>().restaurant_id;
+        // let restaurant_id = match tombstone {
+        //     Some(restaurant) => restaurant.restaurant_id,
+        //     None => {
+        //         let restaurant_id = self.data::<Data>().restaurant_id;
+        //         self.data::<Data>().restaurant_id += 1;
+        //         restaurant_id
+        //     }
+        // };
         // let restaurant = Restaurant {
         //     restaurant_id,
         //     restaurant_account,
         //     restaurant_name,
         //     restaurant_address,
         //     phone_number,
+        //     ..Default::default()
         // };
-        // self.data::<Data>().restaurant_id += 1;
         // self.data::<Data>().restaurant_data.insert(&restaurant_account, &restaurant);
         // self.data::<Data>().restaurant_accounts.insert(&restaurant_id, &restaurant_account);
+        // index_insert!(self.data::<Data>(), active_restaurants, active_restaurant_count, active_restaurant_positions, (), restaurant_id);
         
         // Ok(restaurant_id)
+
+        // Statements below are appended to the expanded code above
+
+        self.data::<Data>().validate_restaurant_media(&media)?;
+
+        let restaurant_account = Self::env().caller();
+        let mut restaurant = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap();
+        restaurant.media = media;
+        self.data::<Data>().restaurant_data.insert(&restaurant_account, &restaurant);
     }
 
     /// Function to read a restaurant infomation
@@ -184,14 +527,19 @@ pub trait RestaurantServiceImpl: Storage<Data> + FoodOrderEvents + PaymentServic
         
         // let restaurant_account = self.data::<Data>().restaurant_accounts.get(&restaurant_id).unwrap();
 
-        // Ok(self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap())
+        // let restaurant = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap();
+        // if restaurant_account == Self::env().caller() {
+        //     Ok(restaurant)
+        // } else {
+        //     Ok(restaurant.redacted())
+        // }
     }
 
     /// Function to read restaurants from given scope
     /// Use read_item_all procedure macro for Restaurant
     #[ink(message)]
     #[read_item_all(Restaurant)]
-    fn read_restaurant_all(&self, from: RestaurantId, to: RestaurantId) -> Result<Vec<Restaurant>, FoodOrderError> {
+    fn read_restaurant_all(&self, cursor: u64, limit: u64) -> Result<Page<Restaurant>, FoodOrderError> {
         // **
 
         // Comments below are current expanded code from the read_item_all macro 
@@ -199,29 +547,46 @@ pub trait RestaurantServiceImpl: Storage<Data> + FoodOrderEvents + PaymentServic
 
         // **
 
-        // ensure!(from < to, FoodOrderError::InvalidParameters);
-        // ensure!(from < self.data::<Data>().restaurant_id, FoodOrderError::InvalidParameters);
+        // let total = self.data::<Data>().active_restaurant_count.get(&()).unwrap_or(0);
+        // let range = self.data::<Data>().page_range(cursor, limit, total)?;
+        // let end = range.end;
 
         // let mut restaurant_list: Vec<Restaurant> = Vec::new();
-        // let start = max(1, from);
-        // let end = min(self.data::<Data>().restaurant_id, to);
-
-        // for i in start..end {
-        //     if self.data::<Data>().restaurant_accounts.contains(&i) {
-        //         let restaurant_account = self.data::<Data>().restaurant_accounts.get(&i).unwrap();
-        //         restaurant_list.push(self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap())
-        //     }
+        // for i in range {
+        //     let restaurant_id = self.data::<Data>().active_restaurants.get(&((), i)).unwrap();
+        //     let restaurant_account = self.data::<Data>().restaurant_accounts.get(&restaurant_id).unwrap();
+        //     restaurant_list.push(self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().redacted());
         // }
 
-        // Ok(restaurant_list)
+        // Ok(Page::new(restaurant_list, end, total))
     }
 
-    /// Function to update a restaurant
+    /// Function to list the restaurants tagged with a category
+    /// At most `limit` restaurants, capped by the maximum page size, are returned per call, pass 0 as `cursor` to start from the beginning
+    #[ink(message)]
+    fn list_restaurants_by_category(&self, category_id: CategoryId, cursor: u64, limit: u64) -> Result<Page<Restaurant>, FoodOrderError> {
+        ensure!(self.data::<Data>().category_data.contains(&category_id), FoodOrderError::CategoryNotExist);
+
+        let total = self.data::<Data>().category_restaurant_count.get(&category_id).unwrap_or(0);
+        let range = self.data::<Data>().page_range(cursor, limit, total)?;
+        let end = range.end;
+
+        let mut restaurant_list: Vec<Restaurant> = Vec::new();
+        for i in range {
+            let restaurant_id = self.data::<Data>().category_restaurants.get(&(category_id, i)).unwrap();
+            let restaurant_account = self.data::<Data>().restaurant_accounts.get(&restaurant_id).unwrap();
+            restaurant_list.push(self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().redacted());
+        }
+
+        Ok(Page::new(restaurant_list, end, total))
+    }
+
+    /// Function to update a restaurant, its categories and its media
     /// Use update_item procedure macro for Restaurant
     #[ink(message)]
     #[update_item(Restaurant)]
     #[modifiers(is_restaurant)]
-    fn update_restaurant(&mut self, restaurant_name: String, restaurant_address: String, phone_number: String) -> Result<(), FoodOrderError> {
+    fn update_restaurant(&mut self, restaurant_name: String, restaurant_address: String, phone_number: String, categories: Vec<CategoryId>, media: RestaurantMedia) -> Result<(), FoodOrderError> {
         // **
 
         // Comments below are current expanded code from the update_item macro 
@@ -245,6 +610,29 @@ pub trait RestaurantServiceImpl: Storage<Data> + FoodOrderEvents + PaymentServic
         // self.data::<Data>().restaurant_data.insert(&restaurant_account, &restaurant);
 
         // Ok(())
+
+        // Statements below are appended to the expanded code above
+
+        ensure!(categories.len() <= MAX_RESTAURANT_CATEGORIES, FoodOrderError::InvalidCategories);
+        self.data::<Data>().validate_restaurant_media(&media)?;
+        for (i, category_id) in categories.iter().enumerate() {
+            ensure!(self.data::<Data>().category_data.contains(category_id), FoodOrderError::CategoryNotExist);
+            ensure!(!categories[..i].contains(category_id), FoodOrderError::InvalidCategories);
+        }
+
+        let restaurant_account = Self::env().caller();
+        let mut restaurant = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap();
+
+        for category_id in restaurant.categories.iter() {
+            index_remove!(self.data::<Data>(), category_restaurants, category_restaurant_count, category_restaurant_positions, *category_id, restaurant.restaurant_id);
+        }
+        for category_id in categories.iter() {
+            index_insert!(self.data::<Data>(), category_restaurants, category_restaurant_count, category_restaurant_positions, *category_id, restaurant.restaurant_id);
+        }
+
+        restaurant.categories = categories;
+        restaurant.media = media;
+        self.data::<Data>().restaurant_data.insert(&restaurant_account, &restaurant);
     }
 
     /// Function to delete a restaurant
@@ -264,15 +652,93 @@ pub trait RestaurantServiceImpl: Storage<Data> + FoodOrderEvents + PaymentServic
 
         // ensure!(self.data::<Data>().restaurant_data.contains(&restaurant_account), FoodOrderError::NotExist);
 
-        // let restaurant = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap();
+        // let mut restaurant = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap();
 
-        // self.data::<Data>().restaurant_data.remove(&restaurant_account);
-        // self.data::<Data>().restaurant_accounts.remove(&restaurant.restaurant_id);
+        // ensure!(self.data::<Data>().restaurant_open_orders.get(&restaurant.restaurant_id).unwrap_or(0) == 0, FoodOrderError::HasOpenOrders);
+
+        // restaurant.deleted = true;
+        // self.data::<Data>().restaurant_data.insert(&restaurant_account, &restaurant);
+        // index_remove!(self.data::<Data>(), active_restaurants, active_restaurant_count, active_restaurant_positions, (), restaurant.restaurant_id);
 
         // Ok(())
+
+        // Statements below are appended to the expanded code above
+
+        for category_id in restaurant.categories.iter() {
+            index_remove!(self.data::<Data>(), category_restaurants, category_restaurant_count, category_restaurant_positions, *category_id, restaurant.restaurant_id);
+        }
+    }
+
+    /// Function to set the weekly opening hours of a restaurant and the UTC offset of its local time
+    /// Orders are only taken within one of the intervals, an empty schedule keeps the restaurant always open
+    #[ink(message)]
+    #[modifiers(is_restaurant)]
+    fn set_opening_hours(&mut self, opening_hours: Vec<OpeningInterval>, utc_offset_minutes: i16) -> Result<(), FoodOrderError> {
+        ensure!(opening_hours.len() <= MAX_OPENING_INTERVALS, FoodOrderError::InvalidParameters);
+        ensure!((-720..=840).contains(&utc_offset_minutes), FoodOrderError::InvalidParameters);
+        for interval in opening_hours.iter() {
+            ensure!(interval.weekday < 7, FoodOrderError::InvalidParameters);
+            ensure!(interval.open_minute < interval.close_minute && interval.close_minute <= 24 * 60, FoodOrderError::InvalidParameters);
+        }
+
+        let restaurant_account = Self::env().caller();
+        let mut restaurant = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap();
+        restaurant.opening_hours = opening_hours;
+        restaurant.utc_offset_minutes = utc_offset_minutes;
+        self.data::<Data>().restaurant_data.insert(&restaurant_account, &restaurant);
+
+        Ok(())
+    }
+
+    /// Function to set the days a restaurant is closed, as local days since 1970-01-01, replacing any previous ones
+    #[ink(message)]
+    #[modifiers(is_restaurant)]
+    fn set_holidays(&mut self, holidays: Vec<u32>) -> Result<(), FoodOrderError> {
+        ensure!(holidays.len() <= MAX_HOLIDAYS, FoodOrderError::InvalidParameters);
+
+        let restaurant_account = Self::env().caller();
+        let mut restaurant = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap();
+        restaurant.holidays = holidays;
+        self.data::<Data>().restaurant_data.insert(&restaurant_account, &restaurant);
+
+        Ok(())
+    }
+
+    /// Function to check whether a restaurant takes orders right now
+    #[ink(message)]
+    fn is_restaurant_open(&self, restaurant_id: RestaurantId) -> Result<bool, FoodOrderError> {
+        ensure!(self.data::<Data>().restaurant_accounts.contains(&restaurant_id), FoodOrderError::NotExist);
+
+        let restaurant_account = self.data::<Data>().restaurant_accounts.get(&restaurant_id).unwrap();
+        let restaurant = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap();
+
+        Ok(!restaurant.deleted && self.data::<Data>().is_open(&restaurant, Self::env().block_timestamp()))
+    }
+
+    /// Function to set the maximum number of orders a restaurant has confirmed but not yet prepared, 0 removes the limit
+    #[ink(message)]
+    #[modifiers(is_restaurant)]
+    fn set_max_open_orders(&mut self, max_open_orders: u32) -> Result<(), FoodOrderError> {
+        let restaurant_account = Self::env().caller();
+        let mut restaurant = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap();
+        restaurant.max_open_orders = max_open_orders;
+        self.data::<Data>().restaurant_data.insert(&restaurant_account, &restaurant);
+
+        Ok(())
+    }
+
+    /// Function to get the number of orders a restaurant has confirmed but not yet prepared or is yet to confirm, and its limit
+    #[ink(message)]
+    fn get_restaurant_load(&self, restaurant_id: RestaurantId) -> Result<RestaurantLoad, FoodOrderError> {
+        ensure!(self.data::<Data>().restaurant_accounts.contains(&restaurant_id), FoodOrderError::NotExist);
+
+        let restaurant_account = self.data::<Data>().restaurant_accounts.get(&restaurant_id).unwrap();
+        let restaurant = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap();
+
+        Ok(self.data::<Data>().restaurant_load(&restaurant))
     }
 
-    /// Function that a restaurant confirms an order
+    /// Function that a restaurant confirms an order, a scheduled order only within its lead window
     #[ink(message)]
     #[modifiers(is_restaurant)]
     fn confirm_order(&mut self, order_id: OrderId, eta: u64) -> Result<OrderId, FoodOrderError> {
@@ -282,12 +748,17 @@ pub trait RestaurantServiceImpl: Storage<Data> + FoodOrderEvents + PaymentServic
         
         // ensure!(self.data::<Data>().restaurant_data.contains(&restaurant_account), FoodOrderError::NotExist);
 
-        let restaurant_id = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().restaurant_id;
+        let restaurant = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap();
+        let restaurant_id = restaurant.restaurant_id;
 
         ensure!(self.data::<Data>().order_data.get(&order_id).unwrap().restaurant_id == restaurant_id, FoodOrderError::CallerIsNotRestaurantOrder);
 
         let mut order = self.data::<Data>().order_data.get(&order_id).unwrap();
-        order.status = OrderStatus::OrderConfirmed;
+        ensure!(order.status == OrderStatus::OrderSubmitted, FoodOrderError::OrderStatusNotSubmitted);
+        ensure!(self.data::<Data>().in_lead_window(&order, Self::env().block_timestamp()), FoodOrderError::OutsideLeadWindow);
+        self.data::<Data>().ensure_capacity_on_confirm(&restaurant, &order)?;
+
+        self.data::<Data>().set_order_status(&mut order, OrderStatus::OrderConfirmed);
         order.eta = eta;
         order.delivery_id = self.data::<Data>().delivery_id;
         self.data::<Data>().order_data.insert(&order_id, &order);
@@ -300,17 +771,115 @@ pub trait RestaurantServiceImpl: Storage<Data> + FoodOrderEvents + PaymentServic
             order_id,
             restaurant_id,
             customer_id: order.customer_id,
-            courier_id: 0,
-            status: DeliveryStatus::Waiting
+            status: DeliveryStatus::Waiting,
+            ..Default::default()
         };
         self.data::<Data>().delivery_id += 1;
         self.data::<Data>().delivery_data.insert(&delivery_id, &delivery);
 
+        index_insert!(self.data::<Data>(), status_deliveries, status_delivery_count, status_delivery_positions, DeliveryStatus::Waiting, delivery_id);
+
         self.emit_request_delivery_event(delivery_id, order_id);
 
         Ok(order_id)
     }
 
+    /// Function that a restaurant rejects a submitted order
+    /// The customer is refunded and the stock taken by the order is put back
+    #[ink(message)]
+    #[modifiers(is_restaurant)]
+    fn reject_order(&mut self, order_id: OrderId) -> Result<OrderId, FoodOrderError> {
+        ensure!(self.data::<Data>().order_data.contains(&order_id), FoodOrderError::OrderNotExist);
+
+        let restaurant_account = Self::env().caller();
+        let restaurant_id = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().restaurant_id;
+
+        ensure!(self.data::<Data>().order_data.get(&order_id).unwrap().restaurant_id == restaurant_id, FoodOrderError::CallerIsNotRestaurantOrder);
+
+        let mut order = self.data::<Data>().order_data.get(&order_id).unwrap();
+        ensure!(order.status == OrderStatus::OrderSubmitted, FoodOrderError::OrderStatusNotSubmitted);
+
+        self.data::<Data>().set_order_status(&mut order, OrderStatus::OrderRejected);
+        self.data::<Data>().order_data.insert(&order_id, &order);
+        self.data::<Data>().release_order(&order);
+
+        self.emit_reject_order_event(order_id);
+
+        // Refund the customer.
+        let customer_account = self.data::<Data>().customer_accounts.get(&order.customer_id).unwrap();
+
+        PaymentServiceImpl::transfer_to(self, customer_account, order.price).expect("Err");
+
+        Ok(order_id)
+    }
+
+    /// Function to get the caller restaurant's orders in the given status, such as new orders waiting for confirmation
+    /// Orders come oldest-first in the order they entered the status, pass 0 as `cursor` to start from the oldest
+    /// At most `limit` queue entries, capped by the maximum page size, are read per call, entries of orders that
+    /// moved on to another status are skipped so a page may hold fewer orders, `total` counts the orders in the status
+    #[ink(message)]
+    fn get_restaurant_orders(&self, status: OrderStatus, cursor: u64, limit: u64) -> Result<Page<Order>, FoodOrderError> {
+        let restaurant_account = Self::env().caller();
+
+        ensure!(self.data::<Data>().restaurant_data.contains(&restaurant_account), FoodOrderError::NotExist);
+
+        let restaurant_id = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().restaurant_id;
+        let key = (restaurant_id, status.clone());
+        let count = self.data::<Data>().restaurant_order_count.get(&key).unwrap_or(0);
+        let head = self.data::<Data>().restaurant_order_head.get(&key).unwrap_or(0);
+        ensure!(cursor <= count, FoodOrderError::InvalidParameters);
+        let range = self.data::<Data>().page_range(max(cursor, head), limit, count)?;
+        let end = range.end;
+
+        let mut order_list: Vec<Order> = Vec::new();
+        for i in range {
+            let order_id = self.data::<Data>().restaurant_orders.get(&(key.clone(), i)).unwrap();
+            let order = self.data::<Data>().order_data.get(&order_id).unwrap();
+            if order.status == status {
+                order_list.push(order);
+            }
+        }
+
+        Ok(Page {
+            items: order_list,
+            next_cursor: if end < count { Some(end) } else { None },
+            total: self.data::<Data>().restaurant_status_count.get(&key).unwrap_or(0),
+        })
+    }
+
+    /// Function that a restaurant offers one of its deliveries to a specific courier
+    /// The courier accepts or declines before the offer timeout, a declined or expired offer goes back to the open board
+    #[ink(message)]
+    #[modifiers(is_restaurant)]
+    fn assign_courier(&mut self, delivery_id: DeliveryId, courier_id: CourierId) -> Result<DeliveryId, FoodOrderError> {
+        ensure!(self.data::<Data>().delivery_data.contains(&delivery_id), FoodOrderError::DeliveryNotExist);
+
+        let restaurant_account = Self::env().caller();
+        let restaurant_id = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().restaurant_id;
+
+        let mut delivery = self.data::<Data>().delivery_data.get(&delivery_id).unwrap();
+        ensure!(delivery.restaurant_id == restaurant_id, FoodOrderError::CallerIsNotRestaurantOrder);
+
+        let now = Self::env().block_timestamp();
+        ensure!(self.data::<Data>().is_up_for_grabs(&delivery, now), FoodOrderError::DeliveryStatusNotWaiting);
+
+        ensure!(self.data::<Data>().courier_accounts.contains(&courier_id), FoodOrderError::NotExist);
+        let courier_account = self.data::<Data>().courier_accounts.get(&courier_id).unwrap();
+        ensure!(!self.data::<Data>().courier_data.get(&courier_account).unwrap().deleted, FoodOrderError::NotExist);
+        self.data::<Data>().ensure_no_courier_conflict(&courier_account, &delivery)?;
+
+        if delivery.status != DeliveryStatus::Offered {
+            self.data::<Data>().set_delivery_status(&mut delivery, DeliveryStatus::Offered);
+        }
+        delivery.offered_to = courier_id;
+        delivery.offer_expires_at = now + self.data::<Data>().courier_offer_timeout;
+        self.data::<Data>().delivery_data.insert(&delivery_id, &delivery);
+
+        self.emit_assign_courier_event(delivery_id, courier_id);
+
+        Ok(delivery_id)
+    }
+
     /// Function that a restaurant finishes cooking of an order
     #[ink(message)]
     #[modifiers(is_restaurant)]
@@ -328,18 +897,64 @@ pub trait RestaurantServiceImpl: Storage<Data> + FoodOrderEvents + PaymentServic
         let mut order = self.data::<Data>().order_data.get(&order_id).unwrap();
         ensure!(order.status == OrderStatus::OrderConfirmed, FoodOrderError::OrderStatusNotConfirmed);
 
-        order.status = OrderStatus::FoodPrepared;
+        self.data::<Data>().set_order_status(&mut order, OrderStatus::FoodPrepared);
         self.data::<Data>().order_data.insert(&order_id, &order);
 
         self.emit_finish_cook_event(order_id);
 
-        let amount = order.price - order.price / (self.data::<Data>().fee_rate as u128);
+        let amount = self.data::<Data>().restaurant_payout(&order);
 
         PaymentServiceImpl::transfer_to(self, restaurant_account, amount).expect("Err");
 
         Ok(order_id)        
     }
 
+    /// Function that a restaurant takes a delivery back from the courier who claimed it, such as a courier who never showed up
+    /// The delivery goes back on the open board, only possible before the courier confirmed the pickup
+    #[ink(message)]
+    #[modifiers(is_restaurant)]
+    fn revoke_claim(&mut self, delivery_id: DeliveryId) -> Result<DeliveryId, FoodOrderError> {
+        ensure!(self.data::<Data>().delivery_data.contains(&delivery_id), FoodOrderError::DeliveryNotExist);
+
+        let restaurant_account = Self::env().caller();
+        let restaurant_id = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().restaurant_id;
+
+        let mut delivery = self.data::<Data>().delivery_data.get(&delivery_id).unwrap();
+        ensure!(delivery.restaurant_id == restaurant_id, FoodOrderError::CallerIsNotRestaurantOrder);
+        ensure!(delivery.status == DeliveryStatus::Claimed || delivery.status == DeliveryStatus::HandoverAcknowledged, FoodOrderError::DeliveryStatusNotClaimed);
+
+        let courier_id = delivery.courier_id;
+        self.data::<Data>().release_delivery(&mut delivery);
+
+        self.emit_claim_released_event(delivery_id, courier_id);
+
+        Ok(delivery_id)
+    }
+
+    /// Function that a restaurant acknowledges handing the prepared food of an order to the courier who claimed its delivery
+    #[ink(message)]
+    #[modifiers(is_restaurant)]
+    fn acknowledge_handover(&mut self, order_id: OrderId) -> Result<OrderId, FoodOrderError> {
+        ensure!(self.data::<Data>().order_data.contains(&order_id), FoodOrderError::OrderNotExist);
+
+        let restaurant_account = Self::env().caller();
+        let restaurant_id = self.data::<Data>().restaurant_data.get(&restaurant_account).unwrap().restaurant_id;
+
+        let order = self.data::<Data>().order_data.get(&order_id).unwrap();
+        ensure!(order.restaurant_id == restaurant_id, FoodOrderError::CallerIsNotRestaurantOrder);
+        ensure!(order.status == OrderStatus::FoodPrepared, FoodOrderError::OrderStatusNotPrepared);
+
+        let mut delivery = self.data::<Data>().delivery_data.get(&order.delivery_id).unwrap();
+        ensure!(delivery.status == DeliveryStatus::Claimed, FoodOrderError::DeliveryStatusNotClaimed);
+
+        self.data::<Data>().set_delivery_status(&mut delivery, DeliveryStatus::HandoverAcknowledged);
+        self.data::<Data>().delivery_data.insert(&delivery.delivery_id, &delivery);
+
+        self.emit_acknowledge_handover_event(delivery.delivery_id, order_id);
+
+        Ok(order_id)
+    }
+
     /// Function that a restaurant delivers an order
     #[ink(message)]
     #[modifiers(is_restaurant)]
@@ -359,7 +974,7 @@ pub trait RestaurantServiceImpl: Storage<Data> + FoodOrderEvents + PaymentServic
         let delivery_status = self.data::<Data>().delivery_data.get(&order.delivery_id).unwrap().status;
         ensure!(delivery_status == DeliveryStatus::PickedUp, FoodOrderError::DeliveryStatusNotPickUp);
 
-        order.status = OrderStatus::FoodDelivered;
+        self.data::<Data>().set_order_status(&mut order, OrderStatus::FoodDelivered);
         self.data::<Data>().order_data.insert(&order_id, &order);
 
         self.emit_deliver_food_event(order_id);
@@ -381,7 +996,8 @@ where
         instance
             .data()
             .restaurant_data
-            .contains(&T::env().caller()),
+            .get(&T::env().caller())
+            .map_or(false, |restaurant| !restaurant.deleted),
         FoodOrderError::NotExist,
     );
     body(instance)
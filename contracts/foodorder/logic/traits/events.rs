@@ -14,6 +14,15 @@ pub trait FoodOrderEvents {
     /// Function that emits FinishCookEvent
     fn emit_finish_cook_event(&self, order_id: OrderId);
 
+    /// Function that emits ClaimDeliveryEvent
+    fn emit_claim_delivery_event(&self, delivery_id: DeliveryId, courier_id: CourierId);
+
+    /// Function that emits ClaimReleasedEvent
+    fn emit_claim_released_event(&self, delivery_id: DeliveryId, courier_id: CourierId);
+
+    /// Function that emits AcknowledgeHandoverEvent
+    fn emit_acknowledge_handover_event(&self, delivery_id: DeliveryId, order_id: OrderId);
+
     /// Function that emits PickupDeliveryEvent
     fn emit_pickup_delivery_event(&self, delivery_id: DeliveryId, courier_id: CourierId);
 
@@ -22,4 +31,16 @@ pub trait FoodOrderEvents {
 
     /// Function that emits AcceptDeliveryEvent
     fn emit_accept_delivery_event(&self, delivery_id: DeliveryId, order_id: OrderId);
+
+    /// Function that emits RejectOrderEvent
+    fn emit_reject_order_event(&self, order_id: OrderId);
+
+    /// Function that emits CancelOrderEvent
+    fn emit_cancel_order_event(&self, order_id: OrderId);
+
+    /// Function that emits AssignCourierEvent
+    fn emit_assign_courier_event(&self, delivery_id: DeliveryId, courier_id: CourierId);
+
+    /// Function that emits CourierOfferWithdrawnEvent
+    fn emit_courier_offer_withdrawn_event(&self, delivery_id: DeliveryId, courier_id: CourierId);
 }
\ No newline at end of file
@@ -55,6 +55,30 @@ mod blockchainfoodorder {
         order_id: OrderId,
     }
 
+    #[ink(event)]
+    pub struct ClaimDeliveryEvent {
+        #[ink(topic)]
+        delivery_id: DeliveryId,
+        #[ink(topic)]
+        courier_id: CourierId,
+    }
+
+    #[ink(event)]
+    pub struct ClaimReleasedEvent {
+        #[ink(topic)]
+        delivery_id: DeliveryId,
+        #[ink(topic)]
+        courier_id: CourierId,
+    }
+
+    #[ink(event)]
+    pub struct AcknowledgeHandoverEvent {
+        #[ink(topic)]
+        delivery_id: DeliveryId,
+        #[ink(topic)]
+        order_id: OrderId,
+    }
+
     #[ink(event)]
     pub struct PickupDeliveryEvent {
         #[ink(topic)]
@@ -71,6 +95,34 @@ mod blockchainfoodorder {
         order_id: OrderId,
     }
 
+    #[ink(event)]
+    pub struct RejectOrderEvent {
+        #[ink(topic)]
+        order_id: OrderId,
+    }
+
+    #[ink(event)]
+    pub struct CancelOrderEvent {
+        #[ink(topic)]
+        order_id: OrderId,
+    }
+
+    #[ink(event)]
+    pub struct AssignCourierEvent {
+        #[ink(topic)]
+        delivery_id: DeliveryId,
+        #[ink(topic)]
+        courier_id: CourierId,
+    }
+
+    #[ink(event)]
+    pub struct CourierOfferWithdrawnEvent {
+        #[ink(topic)]
+        delivery_id: DeliveryId,
+        #[ink(topic)]
+        courier_id: CourierId,
+    }
+
     #[ink(storage)]
     #[derive(Storage, Default)]
     pub struct FoodOrder {
@@ -129,6 +181,27 @@ mod blockchainfoodorder {
             });
         }
 
+        /// Function that emits ClaimDeliveryEvent
+        fn emit_claim_delivery_event(&self, delivery_id: DeliveryId, courier_id: CourierId) {
+            self.env().emit_event(ClaimDeliveryEvent {
+                delivery_id, courier_id,
+            });
+        }
+
+        /// Function that emits ClaimReleasedEvent
+        fn emit_claim_released_event(&self, delivery_id: DeliveryId, courier_id: CourierId) {
+            self.env().emit_event(ClaimReleasedEvent {
+                delivery_id, courier_id,
+            });
+        }
+
+        /// Function that emits AcknowledgeHandoverEvent
+        fn emit_acknowledge_handover_event(&self, delivery_id: DeliveryId, order_id: OrderId) {
+            self.env().emit_event(AcknowledgeHandoverEvent {
+                delivery_id, order_id,
+            });
+        }
+
         /// Function that emits PickupDeliveryEvent
         fn emit_pickup_delivery_event(&self, delivery_id: DeliveryId, courier_id: CourierId) {
             self.env().emit_event(PickupDeliveryEvent {
@@ -149,6 +222,34 @@ mod blockchainfoodorder {
                 delivery_id, order_id,
             });
         }
+
+        /// Function that emits RejectOrderEvent
+        fn emit_reject_order_event(&self, order_id: OrderId) {
+            self.env().emit_event(RejectOrderEvent {
+                order_id,
+            });
+        }
+
+        /// Function that emits CancelOrderEvent
+        fn emit_cancel_order_event(&self, order_id: OrderId) {
+            self.env().emit_event(CancelOrderEvent {
+                order_id,
+            });
+        }
+
+        /// Function that emits AssignCourierEvent
+        fn emit_assign_courier_event(&self, delivery_id: DeliveryId, courier_id: CourierId) {
+            self.env().emit_event(AssignCourierEvent {
+                delivery_id, courier_id,
+            });
+        }
+
+        /// Function that emits CourierOfferWithdrawnEvent
+        fn emit_courier_offer_withdrawn_event(&self, delivery_id: DeliveryId, courier_id: CourierId) {
+            self.env().emit_event(CourierOfferWithdrawnEvent {
+                delivery_id, courier_id,
+            });
+        }
     }
     /// Test
     #[cfg(all(test, feature = "e2e-tests"))]
@@ -162,7 +263,7 @@ mod blockchainfoodorder {
                 courier_service::courierserviceimpl_external::CourierServiceImpl,
                 customer_service::customerserviceimpl_external::CustomerServiceImpl,
                 restaurant_service::restaurantserviceimpl_external::RestaurantServiceImpl,
-                data::{Courier, Customer, Restaurant},
+                data::{Courier, Customer, Restaurant, FoodOrderError},
             },
         };
         use openbrush::{
@@ -220,7 +321,7 @@ mod blockchainfoodorder {
 
             // Create a customer (charlie is a customer)
             let create_customer_msg = build_message::<FoodOrderRef>(address.clone()).call(|contract|
-                contract.create_customer("Customer A".to_string(), "Customer Address".to_string(), "645-234-123".to_string())
+                contract.create_customer("Customer A".to_string(), b"Customer Address".to_vec(), b"645-234-123".to_vec())
             );
             client.call(&ink_e2e::charlie(), create_customer_msg, 0, None).await.expect("failed to create a customer");
 
@@ -233,7 +334,7 @@ mod blockchainfoodorder {
 
             // Create a restaurant (alice)
             let create_restaurant_msg = build_message::<FoodOrderRef>(address.clone()).call(|contract| 
-                contract.create_restaurant("Restaurant A".to_string(), "Restaurant Address".to_string(), "654-987-321".to_string())
+                contract.create_restaurant("Restaurant A".to_string(), "Restaurant Address".to_string(), "654-987-321".to_string(), Default::default())
             );
             client.call(&ink_e2e::alice(), create_restaurant_msg, 0, None).await.expect("failed to create a restaurant");
 
@@ -246,7 +347,7 @@ mod blockchainfoodorder {
 
             // Create a food 
             let create_food_msg = build_message::<FoodOrderRef>(address.clone()).call(|contract| 
-                contract.create_food("Food A".to_string(), "Food Description".to_string(), 1000, 600)
+                contract.create_food("Food A".to_string(), "Food Description".to_string(), 1000, 600, Default::default())
             );
             let result = client.call(&ink_e2e::alice(), create_food_msg, 0, None).await.expect("failed to create a food").return_value();
 
@@ -259,7 +360,7 @@ mod blockchainfoodorder {
 
             // Customer submits an order
             let subtmit_order_msg = build_message::<FoodOrderRef>(address.clone()).call(|contract|
-                contract.submit_order(1, "Delivery Address".to_string())
+                contract.submit_order(1, Vec::new(), b"Delivery Address".to_vec(), None)
             );
             client.call(&ink_e2e::charlie(), subtmit_order_msg, 1000, None).await.expect("failed to sumbit an order");
 
@@ -280,15 +381,25 @@ mod blockchainfoodorder {
 
             assert_eq!(confirm_order_result.unwrap(), 1);
 
-            // Courier pick up order
-            let pickup_delivery_result = {
+            // Courier claims the delivery
+            let claim_delivery_result = {
                 let _msg = build_message::<FoodOrderRef>(address.clone()).call(|contract|
-                    contract.pickup_delivery(1)
+                    contract.claim_delivery(1)
                 );
-                client.call(&ink_e2e::bob(), _msg, 0, None).await.expect("failed to pick up delivery")
+                client.call(&ink_e2e::bob(), _msg, 0, None).await.expect("failed to claim delivery")
             }.return_value();
 
-            assert_eq!(pickup_delivery_result.unwrap(), 1);
+            assert_eq!(claim_delivery_result.unwrap(), 1);
+
+            // Courier cannot pick up the food before it is cooked and handed over
+            let early_pickup_result = {
+                let _msg = build_message::<FoodOrderRef>(address.clone()).call(|contract|
+                    contract.confirm_pickup(1)
+                );
+                client.call_dry_run(&ink_e2e::bob(), &_msg, 0, None).await
+            }.return_value();
+
+            assert_eq!(early_pickup_result, Err(FoodOrderError::DeliveryStatusNotHandedOver));
 
             // Restaurant finishes cook
             let finish_order_result = {
@@ -300,6 +411,26 @@ mod blockchainfoodorder {
 
             assert_eq!(finish_order_result.unwrap(), 1);
 
+            // Restaurant hands the food over to the courier
+            let acknowledge_handover_result = {
+                let _msg = build_message::<FoodOrderRef>(address.clone()).call(|contract|
+                    contract.acknowledge_handover(1)
+                );
+                client.call(&ink_e2e::alice(), _msg, 0, None).await.expect("failed to acknowledge handover")
+            }.return_value();
+
+            assert_eq!(acknowledge_handover_result.unwrap(), 1);
+
+            // Courier picks up the food
+            let confirm_pickup_result = {
+                let _msg = build_message::<FoodOrderRef>(address.clone()).call(|contract|
+                    contract.confirm_pickup(1)
+                );
+                client.call(&ink_e2e::bob(), _msg, 0, None).await.expect("failed to confirm pickup")
+            }.return_value();
+
+            assert_eq!(confirm_pickup_result.unwrap(), 1);
+
             // Restaurant delivers the order
             let deliver_order_result = {
                 let _msg = build_message::<FoodOrderRef>(address.clone()).call(|contract|
@@ -322,5 +453,66 @@ mod blockchainfoodorder {
 
             Ok(())
         }
+
+        #[ink_e2e::test]
+        async fn delete_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let constructor = FoodOrderRef::new();
+            let address = client.instantiate("foodorder", &ink_e2e::alice(), constructor, 0, None).await.expect("instantiate faild").account_id;
+
+            // Create a courier (bob), a customer (charlie) and a restaurant (alice) with a food
+            let create_courier_msg = build_message::<FoodOrderRef>(address.clone()).call(|contract|
+                contract.create_courier("Courier A".to_string(), "Courier Address".to_string(), "123-456-789".to_string())
+            );
+            client.call(&ink_e2e::bob(), create_courier_msg, 0, None).await.expect("failed to create a courier");
+
+            let create_customer_msg = build_message::<FoodOrderRef>(address.clone()).call(|contract|
+                contract.create_customer("Customer A".to_string(), b"Customer Address".to_vec(), b"645-234-123".to_vec())
+            );
+            client.call(&ink_e2e::charlie(), create_customer_msg, 0, None).await.expect("failed to create a customer");
+
+            let create_restaurant_msg = build_message::<FoodOrderRef>(address.clone()).call(|contract| 
+                contract.create_restaurant("Restaurant A".to_string(), "Restaurant Address".to_string(), "654-987-321".to_string(), Default::default())
+            );
+            client.call(&ink_e2e::alice(), create_restaurant_msg, 0, None).await.expect("failed to create a restaurant");
+
+            let create_food_msg = build_message::<FoodOrderRef>(address.clone()).call(|contract| 
+                contract.create_food("Food A".to_string(), "Food Description".to_string(), 1000, 600, Default::default())
+            );
+            client.call(&ink_e2e::alice(), create_food_msg, 0, None).await.expect("failed to create a food");
+
+            // Customer submits an order
+            let subtmit_order_msg = build_message::<FoodOrderRef>(address.clone()).call(|contract|
+                contract.submit_order(1, Vec::new(), b"Delivery Address".to_vec(), None)
+            );
+            client.call(&ink_e2e::charlie(), subtmit_order_msg, 1000, None).await.expect("failed to sumbit an order");
+
+            // Restaurant and customer cannot be deleted while the order is open
+            let delete_restaurant_result = {
+                let _msg = build_message::<FoodOrderRef>(address.clone()).call(|contract| contract.delete_restaurant());
+                client.call_dry_run(&ink_e2e::alice(), &_msg, 0, None).await
+            }.return_value();
+
+            assert_eq!(delete_restaurant_result, Err(FoodOrderError::HasOpenOrders));
+
+            let delete_customer_result = {
+                let _msg = build_message::<FoodOrderRef>(address.clone()).call(|contract| contract.delete_customer());
+                client.call_dry_run(&ink_e2e::charlie(), &_msg, 0, None).await
+            }.return_value();
+
+            assert_eq!(delete_customer_result, Err(FoodOrderError::HasOpenOrders));
+
+            // Courier without deliveries is deleted and kept as a tombstone
+            let delete_courier_msg = build_message::<FoodOrderRef>(address.clone()).call(|contract| contract.delete_courier());
+            client.call(&ink_e2e::bob(), delete_courier_msg, 0, None).await.expect("failed to delete a courier");
+
+            let courier = {
+                let _msg = build_message::<FoodOrderRef>(address.clone()).call(|contract| contract.read_courier_from_id(1));
+                client.call_dry_run(&ink_e2e::alice(), &_msg, 0, None).await
+            }.return_value();
+
+            assert!(courier.unwrap().deleted);
+
+            Ok(())
+        }
     }
 }
\ No newline at end of file